@@ -0,0 +1,329 @@
+use ai_behavior::{Action, ActionArgs, Behavior, Select, Sequence, State, Status};
+use piston_window::{Event, Loop, UpdateArgs};
+use serde::Deserialize;
+
+use crate::components::ship::Actions;
+use crate::ecs::{Ecs, Entity};
+use crate::utils::{Vector, wrapped_offset};
+use crate::{VIEW_H, VIEW_W};
+
+#[derive(Deserialize, Clone)]
+pub struct AutopilotConfig {
+    /// Gap between hulls at which a roid counts as a threat.
+    danger_radius: f64,
+    /// Radius and roid count at which the ship looks for somewhere quieter.
+    crowd_radius: f64,
+    crowd_count: usize,
+    /// Degrees off the lead point that still count as aligned.
+    aim_tolerance: f64,
+    /// Speed the ship coasts at when moving to a safe zone.
+    cruise_speed: f64,
+}
+
+/// The values the shipped config.ron uses.
+impl Default for AutopilotConfig {
+    fn default() -> Self {
+        Self {
+            danger_radius: 60.0,
+            crowd_radius: 160.0,
+            crowd_count: 4,
+            aim_tolerance: 4.0,
+            cruise_speed: 3.0,
+        }
+    }
+}
+
+/// Leaves of the autopilot's behaviour tree. Conditions succeed or fail
+/// straight away, steering tasks write into `Actions` and succeed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Task {
+    ThreatClose,
+    Evade,
+    Crowded,
+    ThrustToSafeZone,
+    AimWithLead,
+    FireWhenAligned,
+}
+
+/// Position, velocity and hull of something the AI can see.
+#[derive(Copy, Clone)]
+pub struct Body {
+    pub pos: Vector,
+    pub vel: Vector,
+    pub rot: f64,
+    pub radius: f64,
+}
+
+/// What a controlled ship knows about the world for one tick.
+pub struct Perception {
+    pub me: Body,
+    pub threats: Vec<Body>,
+    pub laser_speed: f64,
+    /// Whether shots carry the ship's velocity.
+    pub inherit_velocity: bool,
+}
+
+impl Perception {
+    /// Sees the world from `ship`, every roid is a threat.
+    pub fn new(ecs: &Ecs, ship: Entity, laser_speed: f64, inherit_velocity: bool) -> Self {
+        Self {
+            me: body_of(ecs, ship),
+            threats: ecs.roids.entities().map(|roid| body_of(ecs, roid)).collect(),
+            laser_speed,
+            inherit_velocity,
+        }
+    }
+
+    /// Shortest offset from the ship to `pos` on the wrapping playfield.
+    pub fn offset_to(&self, pos: Vector) -> Vector {
+        wrapped_offset(self.me.pos, pos, Vector::new(VIEW_W, VIEW_H))
+    }
+
+    /// Gap between the ship's hull and a threat's.
+    fn gap(&self, threat: &Body) -> f64 {
+        self.offset_to(threat.pos).length() - threat.radius - self.me.radius
+    }
+
+    fn nearest(&self) -> Option<&Body> {
+        self.threats
+            .iter()
+            .min_by(|a, b| self.gap(a).partial_cmp(&self.gap(b)).unwrap())
+    }
+}
+
+fn body_of(ecs: &Ecs, entity: Entity) -> Body {
+    let transform = ecs.transforms.get(entity).copied().unwrap_or_default();
+    Body {
+        pos: transform.pos,
+        vel: ecs.bodies.get(entity).map_or_else(Vector::new_empty, |body| body.vel),
+        rot: transform.rot,
+        radius: ecs.colliders.get(entity).map_or(0.0, |collider| collider.radius),
+    }
+}
+
+/// Drives a ship's `Actions` from a behaviour tree: evade close threats, get
+/// out of crowded areas, otherwise lead the nearest roid and shoot it.
+pub struct Autopilot {
+    config: AutopilotConfig,
+    tree: Behavior<Task>,
+    state: State<Task, ()>,
+}
+
+impl Autopilot {
+    pub fn new(config: AutopilotConfig) -> Self {
+        let tree = Select(vec![
+            Sequence(vec![Action(Task::ThreatClose), Action(Task::Evade)]),
+            Sequence(vec![Action(Task::Crowded), Action(Task::ThrustToSafeZone)]),
+            Sequence(vec![Action(Task::AimWithLead), Action(Task::FireWhenAligned)]),
+        ]);
+        Self {
+            config,
+            state: State::new(tree.clone()),
+            tree,
+        }
+    }
+
+    pub fn update(&mut self, args: UpdateArgs, perception: &Perception) -> Actions {
+        let mut actions = Actions::default();
+        let config = &self.config;
+        let event = Event::Loop(Loop::Update(args));
+        let (status, _) = self.state.event(&event, &mut |args: ActionArgs<Event, Task, ()>| {
+            let done = run_task(args.action, config, perception, &mut actions);
+            (if done { Status::Success } else { Status::Failure }, args.dt)
+        });
+        // the tree is re-evaluated from the root every tick
+        match status {
+            Status::Running => {}
+            _ => self.state = State::new(self.tree.clone()),
+        }
+        actions
+    }
+}
+
+fn run_task(task: &Task, config: &AutopilotConfig, perception: &Perception, actions: &mut Actions) -> bool {
+    let me = &perception.me;
+    match task {
+        Task::ThreatClose => match perception.nearest() {
+            Some(threat) => perception.gap(threat) < config.danger_radius,
+            None => false,
+        },
+        Task::Evade => {
+            let threat = match perception.nearest() {
+                Some(threat) => threat,
+                None => return false,
+            };
+            let away = -perception.offset_to(threat.pos);
+            if turn_towards(actions, me.rot, heading_of(away), 30.0) {
+                actions.fire_boosters = true;
+            }
+            true
+        }
+        Task::Crowded => {
+            let crowd = perception
+                .threats
+                .iter()
+                .filter(|threat| perception.gap(threat) < config.crowd_radius)
+                .count();
+            crowd >= config.crowd_count
+        }
+        Task::ThrustToSafeZone => {
+            let target = perception.offset_to(safest_point(perception));
+            let aligned = turn_towards(actions, me.rot, heading_of(target), 15.0);
+            actions.fire_boosters = aligned && me.vel.length() < config.cruise_speed;
+            true
+        }
+        Task::AimWithLead => {
+            let target = match perception.nearest() {
+                Some(target) => target,
+                None => return false,
+            };
+            let lead = lead_point(perception, target);
+            turn_towards(actions, me.rot, heading_of(lead), config.aim_tolerance / 2.0);
+            true
+        }
+        Task::FireWhenAligned => {
+            let target = match perception.nearest() {
+                Some(target) => target,
+                None => return false,
+            };
+            let lead = lead_point(perception, target);
+            actions.is_shooting = angle_between(me.rot, heading_of(lead)).abs() <= config.aim_tolerance;
+            true
+        }
+    }
+}
+
+/// Offset from the ship to where a laser fired now would meet `target`.
+fn lead_point(perception: &Perception, target: &Body) -> Vector {
+    let p = perception.offset_to(target.pos);
+    // lasers that inherit the ship's velocity are solved in the ship's frame
+    let v = if perception.inherit_velocity { target.vel - perception.me.vel } else { target.vel };
+    let s = perception.laser_speed;
+    let a = v.length_squared() - s * s;
+    let b = 2.0 * p.dot(v);
+    let c = p.length_squared();
+    let t = if a.abs() < 1e-9 {
+        if b.abs() < 1e-9 { 0.0 } else { (-c / b).max(0.0) }
+    } else {
+        let disc = b * b - 4.0 * a * c;
+        if disc < 0.0 {
+            0.0
+        } else {
+            let root = disc.sqrt();
+            let t1 = (-b - root) / (2.0 * a);
+            let t2 = (-b + root) / (2.0 * a);
+            match (t1 > 0.0, t2 > 0.0) {
+                (true, true) => t1.min(t2),
+                (true, false) => t1,
+                (false, true) => t2,
+                _ => 0.0,
+            }
+        }
+    };
+    p + v * t
+}
+
+/// Point on a coarse grid over the playfield furthest from any threat.
+fn safest_point(perception: &Perception) -> Vector {
+    let mut best = perception.me.pos;
+    let mut best_gap = f64::MIN;
+    for i in 1..8 {
+        for j in 1..6 {
+            let point = Vector::new(VIEW_W * f64::from(i) / 8.0, VIEW_H * f64::from(j) / 6.0);
+            let gap = perception
+                .threats
+                .iter()
+                .map(|threat| point.distance(threat.pos) - threat.radius)
+                .fold(f64::MAX, f64::min);
+            if gap > best_gap {
+                best_gap = gap;
+                best = point;
+            }
+        }
+    }
+    best
+}
+
+/// Sets the rotate actions to turn from `rot` towards `desired`, returns
+/// whether the ship is already within `tolerance` degrees.
+fn turn_towards(actions: &mut Actions, rot: f64, desired: f64, tolerance: f64) -> bool {
+    let diff = angle_between(rot, desired);
+    if diff > tolerance {
+        actions.rotate_cw = true;
+    } else if diff < -tolerance {
+        actions.rotate_ccw = true;
+    }
+    diff.abs() <= tolerance
+}
+
+/// Ship rotation that points the nose along `dir`, the sprite faces up at 0.
+fn heading_of(dir: Vector) -> f64 {
+    dir.angle() + 90.0
+}
+
+/// Signed difference from `from` to `to` in degrees, in -180..180.
+fn angle_between(from: f64, to: f64) -> f64 {
+    let diff = (to - from) % 360.0;
+    if diff > 180.0 {
+        diff - 360.0
+    } else if diff < -180.0 {
+        diff + 360.0
+    } else {
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(x: f64, y: f64, vx: f64, vy: f64) -> Body {
+        Body {
+            pos: Vector::new(x, y),
+            vel: Vector::new(vx, vy),
+            rot: 0.0,
+            radius: 10.0,
+        }
+    }
+
+    #[test]
+    fn test_angle_between_wraps() {
+        assert_eq!(angle_between(350.0, 10.0), 20.0);
+        assert_eq!(angle_between(10.0, 350.0), -20.0);
+        assert_eq!(angle_between(-720.0, 90.0), 90.0);
+    }
+
+    #[test]
+    fn test_heading_of() {
+        // the ship's nose points up at rotation 0
+        assert_eq!(heading_of(Vector::new(0.0, -1.0)), 0.0);
+        assert_eq!(heading_of(Vector::new(1.0, 0.0)), 90.0);
+    }
+
+    #[test]
+    fn test_lead_point_moving_target() {
+        let perception = Perception {
+            me: body(100.0, 100.0, 0.0, 0.0),
+            threats: vec![body(200.0, 100.0, 0.0, 5.0)],
+            laser_speed: 10.0,
+            inherit_velocity: true,
+        };
+        let lead = lead_point(&perception, &perception.threats[0]);
+        // the laser travels as far as the lead point in the time the roid gets there
+        let t = lead.y / 5.0;
+        assert!((lead.length() - 10.0 * t).abs() < 1e-6);
+        assert!(lead.y > 0.0);
+    }
+
+    #[test]
+    fn test_offset_wraps_around_edges() {
+        let perception = Perception {
+            me: body(10.0, 10.0, 0.0, 0.0),
+            threats: vec![],
+            laser_speed: 10.0,
+            inherit_velocity: true,
+        };
+        let offset = perception.offset_to(Vector::new(VIEW_W - 10.0, 10.0));
+        assert_eq!(offset.x, -20.0);
+    }
+}
@@ -0,0 +1,92 @@
+use opengl_graphics::{Texture, GlyphCache};
+use piston_window::TextureSettings;
+use image::RgbaImage;
+use std::borrow::Cow;
+use std::fs;
+use std::path::PathBuf;
+
+const SHIP_TEXTURE: &[u8] = include_bytes!("../assets/img/ship2.png");
+const FONT: &[u8] = include_bytes!("../assets/fonts/square.ttf");
+const CONFIG: &str = include_str!("../config.ron");
+const WAVES: &str = include_str!("../waves.ron");
+const ACHIEVEMENTS: &str = include_str!("../achievements.ron");
+
+/// Game assets, read from an optional assets dir and falling back to the copies
+/// compiled into the binary for any file that dir doesn't provide.
+pub struct Assets {
+    dir: Option<PathBuf>,
+    font: Cow<'static, [u8]>,
+}
+
+impl Assets {
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        let font = read_or(&dir, &["fonts", "square.ttf"], FONT);
+        Self {
+            dir,
+            font,
+        }
+    }
+
+    /// The sprite sheet every sprite is cut from.
+    pub fn sprite_sheet(&self) -> Result<RgbaImage, String> {
+        let bytes = read_or(&self.dir, &["img", "ship2.png"], SHIP_TEXTURE);
+        image::load_from_memory(&bytes)
+            .map(|image| image.to_rgba())
+            .map_err(|e| format!("ship texture: {}", e))
+    }
+
+    pub fn ship_texture(&self) -> Result<Texture, String> {
+        Ok(Texture::from_image(&self.sprite_sheet()?, &TextureSettings::new()))
+    }
+
+    pub fn glyph_cache(&self) -> Result<GlyphCache<'_>, String> {
+        GlyphCache::from_bytes(&self.font, (), TextureSettings::new())
+            .map_err(|e| format!("font: {:?}", e))
+    }
+
+    /// The sound file `name` in the assets dir's sounds dir, if there is one.
+    /// No sounds are built in.
+    pub fn sound(&self, name: &str) -> Option<PathBuf> {
+        let path = self.dir.as_ref()?.join("sounds").join(name);
+        Some(path).filter(|path| path.exists())
+    }
+
+    pub fn config(&self) -> Cow<'static, str> {
+        read_str_or(&self.dir, "config.ron", CONFIG)
+    }
+
+    pub fn waves(&self) -> Cow<'static, str> {
+        read_str_or(&self.dir, "waves.ron", WAVES)
+    }
+
+    pub fn achievements(&self) -> Cow<'static, str> {
+        read_str_or(&self.dir, "achievements.ron", ACHIEVEMENTS)
+    }
+}
+
+fn read_str_or(dir: &Option<PathBuf>, name: &str, default: &'static str) -> Cow<'static, str> {
+    match read_or(dir, &[name], default.as_bytes()) {
+        Cow::Borrowed(_) => Cow::Borrowed(default),
+        Cow::Owned(bytes) => Cow::Owned(String::from_utf8_lossy(&bytes).into_owned()),
+    }
+}
+
+fn read_or(dir: &Option<PathBuf>, parts: &[&str], default: &'static [u8]) -> Cow<'static, [u8]> {
+    let mut path = match dir {
+        Some(dir) => dir.clone(),
+        None => return Cow::Borrowed(default),
+    };
+    for part in parts {
+        path.push(part);
+    }
+    if !path.exists() {
+        return Cow::Borrowed(default);
+    }
+    match fs::read(&path) {
+        Ok(bytes) => Cow::Owned(bytes),
+        Err(e) => {
+            println!("Failed to read {}, using built-in copy: {}", path.display(), e);
+            Cow::Borrowed(default)
+        }
+    }
+}
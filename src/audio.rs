@@ -0,0 +1,130 @@
+use std::path::PathBuf;
+
+use crate::assets::Assets;
+use crate::events::{GameEvent, Subscriber};
+
+/// Mixer channels, how many sounds can play over each other. Playing one
+/// with every channel busy panics, so there are plenty and `MIN_GAP` keeps
+/// rapid fire from using them all up.
+const CHANNELS: i32 = 64;
+/// Seconds before the same sound plays again.
+const MIN_GAP: f64 = 0.1;
+const VOLUME: f64 = 0.5;
+
+/// A sound effect the game can play.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sound {
+    Laser,
+    Explosion,
+    ShipDeath,
+    WaveCleared,
+    ExtraLife,
+    GameOver,
+}
+
+impl Sound {
+    pub const ALL: [Sound; 6] = [
+        Sound::Laser, Sound::Explosion, Sound::ShipDeath, Sound::WaveCleared, Sound::ExtraLife, Sound::GameOver,
+    ];
+
+    /// File the sound is read from in the assets' sounds dir.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Sound::Laser => "laser.wav",
+            Sound::Explosion => "explosion.wav",
+            Sound::ShipDeath => "ship_death.wav",
+            Sound::WaveCleared => "wave_cleared.wav",
+            Sound::ExtraLife => "extra_life.wav",
+            Sound::GameOver => "game_over.wav",
+        }
+    }
+}
+
+/// Starts the mixer, binds every sound the assets have a file for and runs
+/// `f` with those sounds, which can then be played. Without any sound files
+/// the mixer isn't started and `f` gets none, so machines without audio
+/// still play.
+pub fn with_sounds<F: FnOnce(Vec<Sound>)>(assets: &Assets, f: F) {
+    let files: Vec<(Sound, PathBuf)> = Sound::ALL.iter()
+        .filter_map(|&sound| assets.sound(sound.file_name()).map(|path| (sound, path)))
+        .collect();
+    if files.is_empty() {
+        return f(Vec::new());
+    }
+    music::start::<(), Sound, _>(CHANNELS, || {
+        for (sound, path) in &files {
+            music::bind_sound_file(*sound, path);
+        }
+        f(files.iter().map(|(sound, _)| *sound).collect());
+    });
+}
+
+/// Turns events into the sounds to play for them, whoever can make a noise
+/// drains them once a frame. The same sound is only queued once a frame, a
+/// spread shot doesn't need to be five times as loud.
+#[derive(Default)]
+pub struct Audio {
+    queued: Vec<Sound>,
+    /// Sounds played in the last `MIN_GAP` seconds and how long until they can play again.
+    playing: Vec<(Sound, f64)>,
+}
+
+impl Audio {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn drain(&mut self) -> std::vec::Drain<'_, Sound> {
+        self.queued.drain(..)
+    }
+
+    /// Plays the queued sounds that are in `bound`, see `with_sounds`,
+    /// `dt` seconds after the last time.
+    pub fn play(&mut self, bound: &[Sound], dt: f64) {
+        for (_, remaining) in &mut self.playing {
+            *remaining -= dt;
+        }
+        self.playing.retain(|(_, remaining)| *remaining > 0.0);
+        for sound in self.queued.drain(..) {
+            if bound.contains(&sound) && !self.playing.iter().any(|(playing, _)| *playing == sound) {
+                music::play_sound(&sound, music::Repeat::Times(0), VOLUME);
+                self.playing.push((sound, MIN_GAP));
+            }
+        }
+    }
+}
+
+impl Subscriber for Audio {
+    fn notify(&mut self, event: &GameEvent) {
+        let sound = match event {
+            GameEvent::LaserFired { .. } => Sound::Laser,
+            GameEvent::RoidDestroyed { .. } => Sound::Explosion,
+            GameEvent::ShipKilled { .. } => Sound::ShipDeath,
+            GameEvent::WaveCleared { .. } => Sound::WaveCleared,
+            GameEvent::ExtraLife { .. } => Sound::ExtraLife,
+            GameEvent::GameOver { .. } => Sound::GameOver,
+            GameEvent::ShotMissed | GameEvent::RoidHit { .. } => return,
+        };
+        if !self.queued.contains(&sound) {
+            self.queued.push(sound);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::Vector;
+
+    #[test]
+    fn test_sounds_are_queued_once_a_frame() {
+        let mut audio = Audio::new();
+        let fired = GameEvent::LaserFired { pos: Vector::new_empty(), rot: 0.0 };
+        audio.notify(&fired);
+        audio.notify(&GameEvent::ShotMissed);
+        audio.notify(&fired);
+        audio.notify(&GameEvent::ExtraLife { lives: 2 });
+        assert_eq!(audio.drain().collect::<Vec<_>>(), vec![Sound::Laser, Sound::ExtraLife]);
+        assert_eq!(audio.drain().next(), None);
+    }
+}
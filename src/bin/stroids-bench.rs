@@ -0,0 +1,165 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use piston_window::UpdateArgs;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+use structopt::StructOpt;
+
+use stroids_clone::ai::Autopilot;
+use stroids_clone::assets::Assets;
+use stroids_clone::components::roid::RoidSize;
+use stroids_clone::env::action_from_index;
+use stroids_clone::load_config;
+use stroids_clone::waves::{Endless, RoidSpawn, Wave, Waves};
+use stroids_clone::world::{Tick, World};
+
+/// Counts every allocation so the bench can report them per tick.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "stroids-bench", about = "Runs the simulation headless and reports how fast it went.")]
+struct Opt {
+    /// Dir containing config.ron, defaults to the built-in config
+    #[structopt(short, long, parse(from_os_str))]
+    config: Option<PathBuf>,
+    /// Number of ticks to simulate
+    #[structopt(short, long, default_value = "100000")]
+    ticks: u64,
+    #[structopt(short, long, default_value = "0")]
+    seed: u64,
+    /// Roids in every wave
+    #[structopt(short, long, default_value = "30")]
+    roids: u32,
+    /// Who flies the ship: autopilot, random or idle
+    #[structopt(short, long, default_value = "autopilot")]
+    input: String,
+    /// Simulated seconds per tick
+    #[structopt(long, default_value = "0.016666666666666666")]
+    dt: f64,
+}
+
+enum Input {
+    Autopilot(Autopilot),
+    Random(Pcg64Mcg),
+    Idle,
+}
+
+fn main() {
+    let opt = Opt::from_args();
+    let config = load_config(opt.config, &Assets::new(None)).unwrap_or_else(|e| {
+        println!("Failed to load config: {}", e);
+        std::process::exit(1);
+    });
+    let mut input = match opt.input.as_str() {
+        "autopilot" => Input::Autopilot(Autopilot::new(config.autopilot_config.clone())),
+        "random" => Input::Random(Pcg64Mcg::seed_from_u64(opt.seed.wrapping_add(1))),
+        "idle" => Input::Idle,
+        other => {
+            println!("Unknown input {}, expected autopilot, random or idle", other);
+            std::process::exit(1);
+        }
+    };
+    let mut world = World::new(config, fixed_waves(opt.roids), opt.seed);
+    let args = UpdateArgs { dt: opt.dt };
+
+    let mut totals = Tick::default();
+    let mut collision_checks = 0u64;
+    let mut deaths = 0u64;
+    let mut waves_cleared = 0u64;
+    let mut movement = Duration::default();
+    let mut collision = Duration::default();
+    let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes_before = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let started = Instant::now();
+    for tick_index in 0..opt.ticks {
+        match &mut input {
+            Input::Autopilot(autopilot) => {
+                let perception = world.perception();
+                world.ship_mut().actions.take_controls(autopilot.update(args, &perception));
+            }
+            // hold each random input for a few ticks like a player would
+            Input::Random(rng) if tick_index % 10 == 0 => {
+                world.ship_mut().actions.take_controls(action_from_index(rng.gen_range(0, 16)));
+            }
+            _ => {}
+        }
+        let mut tick = Tick::default();
+        let movement_start = Instant::now();
+        world.move_entities(args);
+        movement += movement_start.elapsed();
+        let collision_start = Instant::now();
+        world.collide(&mut tick);
+        collision += collision_start.elapsed();
+        world.spawn(&mut tick);
+
+        collision_checks += u64::from(tick.collision_checks);
+        totals.points += tick.points;
+        totals.roids_destroyed += tick.roids_destroyed;
+        deaths += tick.ship_died as u64;
+        waves_cleared += tick.wave_cleared as u64;
+    }
+    let elapsed = started.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations_before;
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes_before;
+
+    let ticks = opt.ticks.max(1) as f64;
+    println!("ticks: {} seed: {} roids: {} input: {}", opt.ticks, opt.seed, opt.roids, opt.input);
+    println!("elapsed: {:.3}s ticks/s: {:.0}", elapsed.as_secs_f64(), ticks / elapsed.as_secs_f64());
+    println!("movement: {:.3}us/tick collision: {:.3}us/tick", micros(movement) / ticks, micros(collision) / ticks);
+    println!("collision checks/tick: {:.1}", collision_checks as f64 / ticks);
+    println!("allocations: {} ({:.2}/tick, {} bytes)", allocations, allocations as f64 / ticks, bytes);
+    println!(
+        "roids destroyed: {} points: {} deaths: {} waves cleared: {}",
+        totals.roids_destroyed, totals.points, deaths, waves_cleared,
+    );
+}
+
+/// Every wave, defined or not, is `roids` large roids.
+fn fixed_waves(roids: u32) -> Waves {
+    let wave = Wave {
+        roids: vec![RoidSpawn { size: RoidSize::Large, count: roids }],
+        speed: None,
+        rules: Vec::new(),
+        wells: Vec::new(),
+    };
+    Waves {
+        waves: vec![wave.clone()],
+        endless: Endless {
+            template: wave,
+            linear: 0.0,
+            quadratic: 0.0,
+            speed_growth: 0.0,
+        },
+    }
+}
+
+fn micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1_000_000.0
+}
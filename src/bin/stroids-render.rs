@@ -0,0 +1,159 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use gif::{Repeat, SetParameter};
+use image::imageops::{resize, FilterType};
+use image::RgbaImage;
+use structopt::StructOpt;
+
+use stroids_clone::assets::Assets;
+use stroids_clone::debug::DebugOverlay;
+use stroids_clone::render::draw_frame;
+use stroids_clone::render::soft::SoftRenderer;
+use stroids_clone::replay::Replay;
+use stroids_clone::world::World;
+use stroids_clone::{load_config, load_waves, VIEW_H, VIEW_W};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "stroids-render", about = "Renders a replay to an animated GIF or a PNG for every frame.")]
+struct Opt {
+    /// Replay saved with --record
+    #[structopt(parse(from_os_str))]
+    replay: PathBuf,
+    /// A .gif file, or a dir to write frame-00000.png and on into
+    #[structopt(short, long, parse(from_os_str))]
+    out: PathBuf,
+    /// Dir containing the config.ron and waves.ron the replay was recorded with, defaults to the built-in ones
+    #[structopt(short, long, parse(from_os_str))]
+    config: Option<PathBuf>,
+    /// Assets dir, any file found here overrides the built-in copy
+    #[structopt(long, parse(from_os_str), env = "STROIDS_ASSETS_PATH")]
+    assets: Option<PathBuf>,
+    #[structopt(long, default_value = "30")]
+    fps: f64,
+    #[structopt(long, default_value = "1024")]
+    width: u32,
+    #[structopt(long, default_value = "768")]
+    height: u32,
+    /// Seconds into the replay to start from
+    #[structopt(long, default_value = "0")]
+    from: f64,
+    /// Seconds into the replay to stop before, defaults to the end
+    #[structopt(long)]
+    to: Option<f64>,
+    /// Draw the debug overlay on top
+    #[structopt(short, long)]
+    debug: bool,
+}
+
+/// Where the frames go.
+enum Output {
+    Pngs(PathBuf),
+    /// The encoder and the centiseconds of delay already handed out, so
+    /// rounding each frame's delay doesn't drift.
+    Gif(gif::Encoder<File>, u64),
+}
+
+impl Output {
+    fn new(path: &Path, width: u32, height: u32) -> Result<Self, String> {
+        let is_gif = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gif"));
+        if !is_gif {
+            fs::create_dir_all(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            return Ok(Output::Pngs(path.to_path_buf()));
+        }
+        let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut encoder = gif::Encoder::new(file, width as u16, height as u16, &[])
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        encoder.set(Repeat::Infinite).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(Output::Gif(encoder, 0))
+    }
+
+    /// Adds frame `index`, shown at `fps` frames per second.
+    fn add(&mut self, index: u64, frame: RgbaImage, fps: f64) -> Result<(), String> {
+        match self {
+            Output::Pngs(dir) => {
+                let path = dir.join(format!("frame-{:05}.png", index));
+                frame.save(&path).map_err(|e| format!("{}: {}", path.display(), e))
+            }
+            Output::Gif(encoder, shown) => {
+                let until = ((index + 1) as f64 * 100.0 / fps).round() as u64;
+                let (width, height) = (frame.width() as u16, frame.height() as u16);
+                let mut pixels = frame.into_raw();
+                let mut gif_frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+                gif_frame.delay = (until - *shown) as u16;
+                *shown = until;
+                encoder.write_frame(&gif_frame).map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+fn main() {
+    let opt = Opt::from_args();
+    if opt.fps <= 0.0 || opt.width == 0 || opt.height == 0 {
+        println!("--fps, --width and --height must be above 0");
+        std::process::exit(1);
+    }
+    if opt.width > u32::from(u16::MAX) || opt.height > u32::from(u16::MAX) {
+        println!("--width and --height must be at most {}", u16::MAX);
+        std::process::exit(1);
+    }
+    let replay = Replay::load(&opt.replay).unwrap_or_else(|e| {
+        println!("Failed to load replay: {}", e);
+        std::process::exit(1);
+    });
+    let assets = Assets::new(opt.assets.clone());
+    let config = load_config(opt.config.clone(), &assets).unwrap_or_else(|e| {
+        println!("Failed to load config: {}", e);
+        std::process::exit(1);
+    });
+    let waves = load_waves(opt.config.clone(), &assets).unwrap_or_else(|e| {
+        println!("Failed to load waves: {}", e);
+        std::process::exit(1);
+    });
+    let mut output = Output::new(&opt.out, opt.width, opt.height).unwrap_or_else(|e| {
+        println!("Failed to create output: {}", e);
+        std::process::exit(1);
+    });
+
+    let mut world = World::new(config, waves, 0);
+    world.restore(replay.start.clone());
+    let mut debug = DebugOverlay::new();
+    debug.enabled = opt.debug;
+    let sprite_sheet = assets.sprite_sheet().unwrap_or_else(|e| {
+        println!("Failed to load assets: {}", e);
+        std::process::exit(1);
+    });
+    let mut renderer = SoftRenderer::new(VIEW_W as u32, VIEW_H as u32, sprite_sheet);
+    let to = opt.to.unwrap_or_else(|| replay.duration());
+    let mut time = 0.0;
+    let mut frames = 0;
+    let mut ticks = replay.ticks.iter();
+    loop {
+        let next_frame = opt.from + frames as f64 / opt.fps;
+        if next_frame >= to {
+            break;
+        }
+        if time >= next_frame {
+            draw_frame(&mut renderer, &world, &debug, &[]);
+            let mut frame = renderer.frame().clone();
+            if frame.dimensions() != (opt.width, opt.height) {
+                frame = resize(&frame, opt.width, opt.height, FilterType::Triangle);
+            }
+            if let Err(e) = output.add(frames, frame, opt.fps) {
+                println!("Failed to write frame {}: {}", frames, e);
+                std::process::exit(1);
+            }
+            frames += 1;
+            continue;
+        }
+        match ticks.next() {
+            Some(tick) => {
+                tick.play(&mut world);
+                time += tick.dt;
+            }
+            None => break,
+        }
+    }
+    println!("rendered {} frames to {}", frames, opt.out.display());
+}
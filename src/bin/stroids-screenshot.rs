@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+
+use piston_window::UpdateArgs;
+use structopt::StructOpt;
+
+use stroids_clone::ai::Autopilot;
+use stroids_clone::assets::Assets;
+use stroids_clone::debug::DebugOverlay;
+use stroids_clone::render::draw_frame;
+use stroids_clone::render::soft::SoftRenderer;
+use stroids_clone::world::World;
+use stroids_clone::{load_config, load_waves, VIEW_H, VIEW_W};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "stroids-screenshot", about = "Plays a seeded game headless and saves a frame of it as a PNG.")]
+struct Opt {
+    /// Dir containing config.ron and waves.ron, defaults to the built-in ones
+    #[structopt(short, long, parse(from_os_str))]
+    config: Option<PathBuf>,
+    /// Assets dir, any file found here overrides the built-in copy
+    #[structopt(long, parse(from_os_str), env = "STROIDS_ASSETS_PATH")]
+    assets: Option<PathBuf>,
+    #[structopt(short, long, default_value = "0")]
+    seed: u64,
+    /// Ticks to simulate before the frame is taken
+    #[structopt(short, long, default_value = "120")]
+    ticks: u64,
+    /// Let the autopilot fly the ship, otherwise it sits still
+    #[structopt(short, long)]
+    autopilot: bool,
+    /// Draw the debug overlay on top
+    #[structopt(short, long)]
+    debug: bool,
+    #[structopt(short, long, parse(from_os_str), default_value = "stroids.png")]
+    out: PathBuf,
+}
+
+fn main() {
+    let opt = Opt::from_args();
+    let assets = Assets::new(opt.assets.clone());
+    let config = load_config(opt.config.clone(), &assets).unwrap_or_else(|e| {
+        println!("Failed to load config: {}", e);
+        std::process::exit(1);
+    });
+    let waves = load_waves(opt.config, &assets).unwrap_or_else(|e| {
+        println!("Failed to load waves: {}", e);
+        std::process::exit(1);
+    });
+    let mut autopilot = Autopilot::new(config.autopilot_config.clone());
+    let mut world = World::new(config, waves, opt.seed);
+    let args = UpdateArgs { dt: 1.0 / 60.0 };
+    for _ in 0..opt.ticks {
+        if opt.autopilot {
+            let perception = world.perception();
+            world.ship_mut().actions.take_controls(autopilot.update(args, &perception));
+        }
+        world.update(args);
+    }
+
+    let mut debug = DebugOverlay::new();
+    debug.enabled = opt.debug;
+    let sprite_sheet = assets.sprite_sheet().unwrap_or_else(|e| {
+        println!("Failed to load assets: {}", e);
+        std::process::exit(1);
+    });
+    let mut renderer = SoftRenderer::new(VIEW_W as u32, VIEW_H as u32, sprite_sheet);
+    draw_frame(&mut renderer, &world, &debug, &[]);
+    if let Err(e) = renderer.save_png(&opt.out) {
+        println!("Failed to save screenshot: {}", e);
+        std::process::exit(1);
+    }
+}
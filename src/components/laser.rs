@@ -1,64 +1,63 @@
 extern crate find_folder;
 
-use opengl_graphics::{Texture, GlGraphics};
-use sprite::{Scene, Sprite,};
-use piston_window::{Context, UpdateArgs};
-use std::rc::Rc;
-use serde::Deserialize;
-use uuid::Uuid;
-use crate::utils::{Vector, angle_to_vector, loop_pos};
-use crate::{VIEW_H, VIEW_W};
+use serde::{Deserialize, Serialize};
+use crate::weapons::{Weapon, WeaponKind};
 
+const SPRITE_RECT: [f64; 4] = [334.0, 223.0, 4.0, 4.0];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum FireMode {
+    /// Holding the trigger keeps firing.
+    #[default]
+    FullAuto,
+    /// One shot per press of the trigger.
+    SemiAuto,
+}
+
+/// The classic laser, always the ship's first weapon, and the rules every
+/// weapon's shots follow.
 #[derive(Deserialize, Clone)]
 pub struct LaserConfig {
     laser_lifetime: f64,
     laser_speed: f64,
+    /// Mass handed to the roid a laser hits, roids weigh their diameter squared.
+    #[serde(default)]
+    laser_mass: f64,
+    /// Shots that can be on screen at once, unlimited when left out.
+    #[serde(default)]
+    pub max_shots: Option<usize>,
+    /// Shots wrap around the edges, otherwise they die there.
+    #[serde(default = "default_true")]
+    pub wrap_shots: bool,
+    /// Shots carry the ship's velocity on top of their own.
+    #[serde(default = "default_true")]
+    pub inherit_velocity: bool,
+    #[serde(default)]
+    pub fire_mode: FireMode,
 }
 
-#[derive(Clone)]
-pub struct Laser {
-    pub pos: Vector,
-    vel: Vector,
-    size: Vector,
-    rot: f64,
-    pub life: f64,
-    sprite_id: Uuid,
-    diameter: f64,
+fn default_true() -> bool {
+    true
 }
 
-impl Laser {
-    pub fn new(base_vel: Vector, config: LaserConfig, pos: Vector, fake_rot: f64, tex: Rc<Texture>, scene: &mut Scene<Texture>) -> Self {
-        let mut sprite = Sprite::from_texture_rect(tex, [334.0, 223.0, 4.0, 4.0]);
-        let sprite_box = sprite.bounding_box();
-        sprite.set_scale(0.5, 0.5);
-        let rot = fake_rot - 90.0;
-
-        Self {
-            pos,
-            rot,
-            life: config.laser_lifetime,
-            vel: angle_to_vector(config.laser_speed, rot) + base_vel,
-            sprite_id: scene.add_child(sprite),
-            size: Vector::new(sprite_box[2], sprite_box[3]),
-            diameter: sprite_box[2].max(sprite_box[3]),
-        }
-    }
-
-    pub fn draw(&mut self, context: Context, graphics: &mut GlGraphics, scene: &mut Scene<Texture>) {
-        let sprite = scene.child_mut(self.sprite_id).unwrap();
-        sprite.set_position(self.pos.x, self.pos.y);
-        sprite.set_rotation(self.rot);
-        sprite.draw(
-            context.transform,
-            graphics
-        );
+impl LaserConfig {
+    pub fn speed(&self) -> f64 {
+        self.laser_speed
     }
-    pub fn update(&mut self, args: UpdateArgs) {
-        self.pos += self.vel * args.dt.into() * 60.0.into();
-        self.pos = loop_pos(self.pos, self.diameter, Vector::new(VIEW_W, VIEW_H));
 
-        if self.life > 0.0 {
-            self.life = (self.life - args.dt).max(0.0);
+    /// Single shot weapon firing this laser every `cooldown` seconds.
+    pub fn weapon(&self, cooldown: f64) -> Weapon {
+        Weapon {
+            name: "Laser".to_string(),
+            kind: WeaponKind::Single,
+            cooldown,
+            speed: self.laser_speed,
+            lifetime: self.laser_lifetime,
+            sprite: SPRITE_RECT,
+            scale: 0.5,
+            damage: 1,
+            mass: self.laser_mass,
+            ammo: None,
         }
     }
-}
\ No newline at end of file
+}
@@ -1,10 +1,70 @@
-use piston_window::{Context};
-use opengl_graphics::GlGraphics;
+use serde::{Deserialize, Serialize};
+use crate::render::Renderer;
+use crate::utils::Vector;
 
 pub mod ship;
 pub mod laser;
+pub mod projectile;
 pub mod roid;
+pub mod well;
 
-pub trait Drawable {
-    fn draw(&self, context: Context, graphics: &mut  GlGraphics);
-}
\ No newline at end of file
+/// Where an entity is and which way it faces, in degrees.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Transform {
+    pub pos: Vector,
+    pub rot: f64,
+}
+
+/// Anything that moves. Velocities are in px per 1/60s and degrees per second.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Body {
+    pub vel: Vector,
+    pub rot_vel: f64,
+    pub mass: f64,
+    /// How far past the edge of the playfield it goes before wrapping around.
+    pub wrap_margin: f64,
+}
+
+impl Body {
+    /// Soaks up something of `mass` moving at `vel`, keeping the combined momentum.
+    pub fn absorb(&mut self, vel: Vector, mass: f64) {
+        self.vel = (self.vel * self.mass + vel * mass) / (self.mass + mass);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Collider {
+    pub radius: f64,
+}
+
+/// Seconds an entity has left before it is despawned.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Lifetime {
+    pub remaining: f64,
+}
+
+/// A part of the sprite sheet drawn at the entity's transform.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Renderable {
+    rect: [f64; 4],
+    scale: f64,
+    pub tint: Option<[f32; 3]>,
+}
+
+impl Renderable {
+    pub fn new(rect: [f64; 4], scale: f64) -> Self {
+        Self {
+            rect,
+            scale,
+            tint: None,
+        }
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub fn draw<R: Renderer>(&self, transform: &Transform, renderer: &mut R) {
+        renderer.sprite(self.rect, transform.pos.round(), transform.rot, self.scale, self.tint);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::components::{Body, Collider, Lifetime, Renderable, Transform};
+use crate::ecs::{Ecs, Entity};
+use crate::utils::{Vector, angle_to_vector};
+use crate::weapons::{Weapon, WeaponKind};
+use serde::{Deserialize, Serialize};
+
+/// Something fired by a weapon that damages the roids it hits.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Projectile {
+    pub damage: u32,
+    /// Carries on after a hit instead of being used up.
+    pub pierce: bool,
+    /// Roids a piercing projectile already hit, so it only hits each once.
+    pub hits: Vec<Entity>,
+}
+
+/// Despawned instead of wrapped around when it leaves the playfield.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Bounded;
+
+/// Steers the projectile towards a roid it locks on to and blows up on impact.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Missile {
+    /// Degrees per second.
+    pub turn_rate: f64,
+    /// Degrees either side of the heading a target has to be in, and how far away.
+    pub cone: f64,
+    pub range: f64,
+    /// Seconds of steering left.
+    pub fuel: f64,
+    pub blast_radius: f64,
+    pub target: Option<Entity>,
+}
+
+impl Projectile {
+    /// Fires one of `weapon`'s projectiles from `pos` along the ship rotation `fake_rot`.
+    pub fn spawn(ecs: &mut Ecs, weapon: &Weapon, base_vel: Vector, pos: Vector, fake_rot: f64) -> Entity {
+        let rot = fake_rot - 90.0;
+        let radius = weapon.sprite[2].max(weapon.sprite[3]) * weapon.scale / 2.0;
+        let entity = ecs.spawn();
+        ecs.transforms.insert(entity, Transform { pos, rot });
+        ecs.bodies.insert(entity, Body {
+            vel: angle_to_vector(weapon.speed, rot) + base_vel,
+            rot_vel: 0.0,
+            mass: weapon.mass,
+            wrap_margin: radius * 2.0,
+        });
+        ecs.colliders.insert(entity, Collider { radius });
+        ecs.renderables.insert(entity, Renderable::new(weapon.sprite, weapon.scale));
+        ecs.lifetimes.insert(entity, Lifetime { remaining: weapon.lifetime });
+        ecs.projectiles.insert(entity, Projectile {
+            damage: weapon.damage,
+            pierce: weapon.pierces(),
+            hits: Vec::new(),
+        });
+        if let WeaponKind::Missile { turn_rate, cone, range, fuel, blast_radius } = weapon.kind {
+            ecs.missiles.insert(entity, Missile {
+                turn_rate,
+                cone,
+                range,
+                fuel,
+                blast_radius,
+                target: None,
+            });
+        }
+        entity
+    }
+}
@@ -1,17 +1,45 @@
 
 extern crate find_folder;
 
-use opengl_graphics::{Texture, GlGraphics};
-use sprite::{Scene, Sprite,};
-use piston_window::{Context, UpdateArgs};
-use std::rc::Rc;
-use uuid::Uuid;
-use crate::utils::{Vector, angle_to_vector, loop_pos, point_within_radius};
+use crate::components::{Body, Collider, Renderable, Transform};
+use crate::components::well::WellKind;
+use crate::ecs::{Ecs, Entity};
+use crate::utils::{Vector, angle_to_vector, wrapped_offset};
 use crate::{VIEW_H, VIEW_W};
-use rand::rngs::ThreadRng;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use rand::Rng;
 
+const SPRITE_RECT: [f64; 4] = [200.0, 200.0, 50.0, 50.0];
+/// Random spots tried for a new roid before settling for the clearest one.
+const SPAWN_ATTEMPTS: u32 = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RoidSize {
+    Large,
+    Medium,
+    Small,
+}
+
+impl RoidSize {
+    /// Multiplier applied on top of the random `min_scale..max_scale` scale.
+    pub fn scale(self) -> f64 {
+        match self {
+            RoidSize::Large => 1.0,
+            RoidSize::Medium => 0.6,
+            RoidSize::Small => 0.35,
+        }
+    }
+
+    /// Size of the fragments this size breaks into.
+    pub fn smaller(self) -> Option<RoidSize> {
+        match self {
+            RoidSize::Large => Some(RoidSize::Medium),
+            RoidSize::Medium => Some(RoidSize::Small),
+            RoidSize::Small => None,
+        }
+    }
+}
+
 #[derive(Clone, Deserialize)]
 pub struct RoidConfig {
     min_speed: f64,
@@ -20,60 +48,225 @@ pub struct RoidConfig {
     min_scale: f64,
     max_scale: f64,
     min_spawn_mag: f64,
-    max_spawn_mag: f64,
+    /// Roids bounce off each other instead of passing through.
+    #[serde(default)]
+    collide: bool,
+    /// Fraction of the closing speed kept after a bounce, 1 is perfectly elastic.
+    #[serde(default = "default_restitution")]
+    restitution: f64,
+    /// Lasers it takes to destroy a large, medium and small roid.
+    #[serde(default = "default_health")]
+    health: (u32, u32, u32),
+    /// Destroyed roids break into two of the next size down.
+    #[serde(default)]
+    split: bool,
+}
+
+fn default_restitution() -> f64 {
+    1.0
 }
 
+fn default_health() -> (u32, u32, u32) {
+    (1, 1, 1)
+}
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Roid {
-    pub pos: Vector,
-    vel: Vector,
-    rot: f64,
-    rot_vel: f64,
-    sprite_id: Uuid,
-    pub diameter: f64,
+    pub size: RoidSize,
+    pub health: u32,
+}
+
+impl RoidConfig {
+    /// Copy of this config with the speed range scaled by `speed` and spin by `spin`.
+    pub fn scaled(&self, speed: f64, spin: f64) -> Self {
+        Self {
+            min_speed: self.min_speed * speed,
+            max_speed: self.max_speed * speed,
+            max_rot: self.max_rot * spin,
+            ..self.clone()
+        }
+    }
+
+    /// Restitution to bounce roids with, or `None` when they pass through each other.
+    pub fn bounce(&self) -> Option<f64> {
+        if self.collide {
+            Some(self.restitution)
+        } else {
+            None
+        }
+    }
+
+    fn health(&self, size: RoidSize) -> u32 {
+        let health = match size {
+            RoidSize::Large => self.health.0,
+            RoidSize::Medium => self.health.1,
+            RoidSize::Small => self.health.2,
+        };
+        health.max(1)
+    }
+
+    pub fn with_speed(&self, min_speed: f64, max_speed: f64) -> Self {
+        Self {
+            min_speed,
+            max_speed,
+            ..self.clone()
+        }
+    }
 }
 
 impl Roid {
-    pub fn new(config: RoidConfig, tex: Rc<Texture>, scene: &mut Scene<Texture>, rng: &mut ThreadRng) -> Self {
-        let mut sprite = Sprite::from_texture_rect(tex, [200.0, 200.0, 50.0, 50.0]);
+    /// Spawns a roid somewhere away from the middle of the screen and clear
+    /// of every black hole already in `ecs`, or the clearest spot found when
+    /// `SPAWN_ATTEMPTS` tries don't find one.
+    pub fn spawn<R: Rng + ?Sized>(ecs: &mut Ecs, config: &RoidConfig, size: RoidSize, rng: &mut R) -> Entity {
+        let max_radius = SPRITE_RECT[2].max(SPRITE_RECT[3]) * config.max_scale * size.scale() / 2.0;
         let mut pos = rng_vector(rng);
-        while point_within_radius(pos, Vector::new(VIEW_W / 2.0, VIEW_H / 2.0), config.min_spawn_mag) {
-            pos = rng_vector(rng);
+        let mut best = clearance(ecs, config, pos, max_radius);
+        for _ in 1..SPAWN_ATTEMPTS {
+            if best > 0.0 {
+                break;
+            }
+            let candidate = rng_vector(rng);
+            let candidate_clearance = clearance(ecs, config, candidate, max_radius);
+            if candidate_clearance > best {
+                pos = candidate;
+                best = candidate_clearance;
+            }
         }
-        let sprite_box = sprite.bounding_box();
         let speed = rng.gen_range(config.min_speed, config.max_speed);
-        let scale = rng.gen_range(config.min_scale, config.max_scale);
-        sprite.set_scale(scale, scale);
+        let scale = rng.gen_range(config.min_scale, config.max_scale) * size.scale();
+        let vel = angle_to_vector(speed, rng.gen_range(0.0, 360.0));
+        Self::spawn_scaled(ecs, config, size, scale, pos, vel, rng)
+    }
 
-        Self {
-            pos,
-            rot: 0.0,
+    fn spawn_scaled<R: Rng + ?Sized>(ecs: &mut Ecs, config: &RoidConfig, size: RoidSize, scale: f64, pos: Vector, vel: Vector, rng: &mut R) -> Entity {
+        let diameter = SPRITE_RECT[2].max(SPRITE_RECT[3]) * scale;
+        let entity = ecs.spawn();
+        ecs.transforms.insert(entity, Transform { pos, rot: 0.0 });
+        ecs.bodies.insert(entity, Body {
+            vel,
             rot_vel: rng.gen_range(-config.max_rot, config.max_rot),
-            vel: angle_to_vector(speed, rng.gen_range(0.0, 360.0)),
-            sprite_id: scene.add_child(sprite),
-            diameter: sprite_box[2].max(sprite_box[3]) * scale,
-        }
+            mass: diameter * diameter,
+            wrap_margin: diameter,
+        });
+        ecs.colliders.insert(entity, Collider { radius: diameter / 2.0 });
+        ecs.renderables.insert(entity, Renderable::new(SPRITE_RECT, scale));
+        ecs.roids.insert(entity, Roid {
+            size,
+            health: config.health(size),
+        });
+        entity
     }
 
-    pub fn draw(&mut self, context: Context, graphics: &mut GlGraphics, scene: &mut Scene<Texture>) {
-        let sprite = scene.child_mut(self.sprite_id).unwrap();
-        sprite.set_position(self.pos.x, self.pos.y);
-        sprite.set_rotation(self.rot);
-        sprite.draw(
-            context.transform,
-            graphics
-        );
+    /// Takes `damage`, returning true once the roid is destroyed.
+    pub fn hit(&mut self, damage: u32) -> bool {
+        self.health = self.health.saturating_sub(damage);
+        self.health == 0
     }
-    pub fn update(&mut self, args: UpdateArgs) {
-        self.pos += self.vel * args.dt.into() * 60.0.into();
-        self.rot += self.rot_vel * args.dt;
-        self.pos = loop_pos(self.pos, self.diameter, Vector::new(VIEW_W, VIEW_H));
+
+    /// Spawns the two fragments `entity` breaks into, flying apart on top of
+    /// its own velocity. Small roids and configs without `split` leave none.
+    pub fn split<R: Rng + ?Sized>(ecs: &mut Ecs, entity: Entity, config: &RoidConfig, rng: &mut R) -> Vec<Entity> {
+        let parent = match ecs.roids.get(entity) {
+            Some(roid) => roid.size,
+            None => return Vec::new(),
+        };
+        let size = match parent.smaller() {
+            Some(size) if config.split => size,
+            _ => return Vec::new(),
+        };
+        let pos = ecs.transforms.get(entity).map_or_else(Vector::new_empty, |t| t.pos);
+        let vel = ecs.bodies.get(entity).map_or_else(Vector::new_empty, |b| b.vel);
+        let radius = ecs.colliders.get(entity).map_or(0.0, |c| c.radius);
+        let scale = ecs.renderables.get(entity).map_or(1.0, |r| r.scale()) * size.scale() / parent.scale();
+        let angle = rng.gen_range(0.0, 360.0);
+        let kick = rng.gen_range(config.min_speed, config.max_speed);
+        [angle, angle + 180.0]
+            .iter()
+            .map(|&theta| {
+                let pos = pos + angle_to_vector(radius / 2.0, theta);
+                let vel = vel + angle_to_vector(kick, theta);
+                Self::spawn_scaled(ecs, config, size, scale, pos, vel, rng)
+            })
+            .collect()
     }
 }
 
-fn rng_vector(rng: &mut ThreadRng) -> Vector {
+/// How far a roid of `radius` at `pos` is from where roids can't start, the
+/// middle of the screen and the black holes' horizons, negative inside them.
+fn clearance(ecs: &Ecs, config: &RoidConfig, pos: Vector, radius: f64) -> f64 {
+    let middle = pos.distance(Vector::new(VIEW_W / 2.0, VIEW_H / 2.0)) - config.min_spawn_mag;
+    ecs.wells.iter()
+        .filter(|(_, well)| well.kind == WellKind::BlackHole)
+        .filter_map(|(entity, well)| {
+            let offset = wrapped_offset(pos, ecs.transforms.get(entity)?.pos, Vector::new(VIEW_W, VIEW_H));
+            Some(offset.length() - well.horizon - radius)
+        })
+        .fold(middle, f64::min)
+}
+
+fn rng_vector<R: Rng + ?Sized>(rng: &mut R) -> Vector {
     Vector {
         x: rng.gen_range(0.0, VIEW_W),
         y: rng.gen_range(0.0, VIEW_H),
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::well::{Well, WellSpawn};
+
+    #[test]
+    fn test_split_into_smaller_roids() {
+        let config: RoidConfig = ron::de::from_str(
+            "(min_speed: 1.0, max_speed: 2.0, max_rot: 10.0, min_scale: 1.0, max_scale: 2.0, \
+             min_spawn_mag: 100.0, health: (3, 2, 1), split: true)",
+        ).unwrap();
+        let mut rng = rand_pcg::Pcg64Mcg::new(1);
+        let mut ecs = Ecs::new();
+        let parent = Roid::spawn(&mut ecs, &config, RoidSize::Large, &mut rng);
+        let roid = ecs.roids.get_mut(parent).unwrap();
+        assert_eq!(roid.health, 3);
+        assert!(!roid.hit(1));
+        let parent_radius = ecs.colliders.get(parent).unwrap().radius;
+        let fragments = Roid::split(&mut ecs, parent, &config, &mut rng);
+        assert_eq!(fragments.len(), 2);
+        for fragment in &fragments {
+            assert_eq!(ecs.roids.get(*fragment).unwrap().size, RoidSize::Medium);
+            assert_eq!(ecs.roids.get(*fragment).unwrap().health, 2);
+            assert!((ecs.colliders.get(*fragment).unwrap().radius - parent_radius * 0.6).abs() < 1e-9);
+        }
+        let drift = (ecs.bodies.get(fragments[0]).unwrap().vel + ecs.bodies.get(fragments[1]).unwrap().vel) / 2.0;
+        assert!(drift.distance(ecs.bodies.get(parent).unwrap().vel) < 1e-9);
+        let small = Roid::split(&mut ecs, fragments[0], &config, &mut rng);
+        assert_eq!(ecs.roids.get(small[0]).unwrap().size, RoidSize::Small);
+        assert!(Roid::split(&mut ecs, small[0], &config, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn test_spawns_clear_of_black_holes() {
+        let config: RoidConfig = ron::de::from_str(
+            "(min_speed: 1.0, max_speed: 2.0, max_rot: 10.0, min_scale: 1.0, max_scale: 2.0, min_spawn_mag: 100.0)",
+        ).unwrap();
+        let mut rng = rand_pcg::Pcg64Mcg::new(2);
+        let mut ecs = Ecs::new();
+        let hole = Vector::new(200.0, 200.0);
+        Well::spawn(&mut ecs, &WellSpawn { kind: WellKind::BlackHole, pos: hole, strength: 0.0, horizon: 150.0 });
+        for _ in 0..50 {
+            let roid = Roid::spawn(&mut ecs, &config, RoidSize::Large, &mut rng);
+            let pos = ecs.transforms.get(roid).unwrap().pos;
+            let radius = ecs.colliders.get(roid).unwrap().radius;
+            assert!(wrapped_offset(pos, hole, Vector::new(VIEW_W, VIEW_H)).length() >= 150.0 + radius);
+        }
+    }
+
+    #[test]
+    fn test_spawns_somewhere_when_nowhere_is_clear() {
+        let config: RoidConfig = ron::de::from_str(
+            "(min_speed: 1.0, max_speed: 2.0, max_rot: 10.0, min_scale: 1.0, max_scale: 2.0, min_spawn_mag: 5000.0)",
+        ).unwrap();
+        let mut ecs = Ecs::new();
+        Roid::spawn(&mut ecs, &config, RoidSize::Large, &mut rand_pcg::Pcg64Mcg::new(3));
+        assert_eq!(ecs.roids.len(), 1);
+    }
+}
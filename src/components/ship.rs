@@ -1,13 +1,14 @@
-use opengl_graphics::{Texture, GlGraphics};
-use sprite::{Scene, Sprite,};
-use piston_window::{Context, UpdateArgs};
-use serde::Deserialize;
-use std::rc::Rc;
-use uuid::Uuid;
-use crate::utils::{Vector, degree_to_radians, angle_to_vector, loop_pos};
+use piston_window::UpdateArgs;
+use serde::{Deserialize, Serialize};
+use crate::components::{Body, Collider, Renderable, Transform};
+use crate::ecs::{Ecs, Entity};
+use crate::utils::{Vector, angle_to_vector};
+use crate::debug::rotated_box;
 use crate::{VIEW_H, VIEW_W};
 
-#[derive(Deserialize, Clone)]
+const SPRITE_RECT: [f64; 4] = [13.0, 4.0, 67.0, 80.0];
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ShipConfig {
     scale: f64,
     rotation_increment: f64,
@@ -15,95 +16,142 @@ pub struct ShipConfig {
     blink_timer: f64,
     laser_timer: f64,
     max_velocity: f64,
+    /// Fraction of the velocity lost per second, 0 coasts forever.
+    #[serde(default)]
+    drag: f64,
+    /// Degrees per second squared, rotation is instant at `rotation_increment` without it.
+    #[serde(default)]
+    angular_acceleration: Option<f64>,
+    /// Acceleration when thrusting backwards, 0 disables reverse thrust.
+    #[serde(default)]
+    reverse_thrust_increment: f64,
+    /// Speed lost per second while braking, 0 disables the brake.
+    #[serde(default)]
+    brake_increment: f64,
+}
+
+impl ShipConfig {
+    /// Seconds between shots of the classic laser.
+    pub fn laser_timer(&self) -> f64 {
+        self.laser_timer
+    }
 }
 
-pub struct Ship{
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Ship {
     config: ShipConfig,
-    sprite_id: Uuid,
-    pub pos: Vector,
-    pub rot: f64,
-    pub vel: Vector,
     pub actions: Actions,
     tinted: bool,
     tint_rgb: [f32; 3],
     blink_cooldown: f64,
-    laser_cooldown: f64,
-    pub radius: f64,
+    size: Vector,
     pub dead: bool,
+    pub boosters_enabled: bool,
 }
 
-#[derive(Default)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct Actions {
     pub rotate_cw: bool,
     pub rotate_ccw: bool,
     pub fire_boosters: bool,
+    pub reverse_thrust: bool,
+    pub brake: bool,
     pub is_shooting: bool,
     pub is_blinking: bool,
 }
 
-impl Ship {
-    pub fn new(config: ShipConfig, scene: &mut Scene<Texture>, tex: Rc<Texture>) -> Self {
-        let pos = Vector::new(
-            VIEW_W / 2.0,
-            VIEW_H / 2.0
-        );
-        let mut sprite = Sprite::from_texture_rect(tex, [13.0, 4.0, 67.0, 80.0]);
-        sprite.set_scale(config.scale, config.scale);
-        let sprite_box = sprite.bounding_box();
+impl Actions {
+    /// Copies the ship controls from `other`, leaving the rest alone.
+    pub fn take_controls(&mut self, other: Actions) {
+        self.rotate_cw = other.rotate_cw;
+        self.rotate_ccw = other.rotate_ccw;
+        self.fire_boosters = other.fire_boosters;
+        self.reverse_thrust = other.reverse_thrust;
+        self.brake = other.brake;
+        self.is_shooting = other.is_shooting;
+    }
+}
 
+impl Ship {
+    pub fn new(config: ShipConfig) -> Self {
+        let size = Vector::new(SPRITE_RECT[2], SPRITE_RECT[3]) * config.scale;
         Self {
             config,
-            sprite_id: scene.add_child(sprite),
-            pos,
-            rot: 0.0,
-            vel: Vector::new_empty(),
             actions: Actions::default(),
             tinted: false,
             tint_rgb: [1.0, 0.0, 0.0],
             blink_cooldown: 0.0,
-            laser_cooldown: 0.0,
-            radius: sprite_box[2].max(sprite_box[3]),
+            size,
             dead: false,
+            boosters_enabled: true,
         }
     }
 
-    pub fn draw(&mut self, context: Context, graphics: &mut GlGraphics, scene: &mut Scene<Texture>) {
-        let sprite = scene.child_mut(self.sprite_id).unwrap();
-        let normed = self.pos.round();
-        sprite.set_position(normed.x, normed.y);
-        sprite.set_rotation(self.rot);
+    /// Spawns a ship in the middle of the playfield.
+    pub fn spawn(ecs: &mut Ecs, config: ShipConfig) -> Entity {
+        let ship = Ship::new(config);
+        let radius = ship.radius();
+        let entity = ecs.spawn();
+        ecs.transforms.insert(entity, Transform {
+            pos: Vector::new(VIEW_W / 2.0, VIEW_H / 2.0),
+            rot: 0.0,
+        });
+        ecs.bodies.insert(entity, Body {
+            mass: (radius * 2.0).powi(2),
+            wrap_margin: radius,
+            ..Body::default()
+        });
+        ecs.colliders.insert(entity, Collider { radius });
+        ecs.renderables.insert(entity, Renderable::new(SPRITE_RECT, ship.config.scale));
+        ecs.ships.insert(entity, ship);
+        entity
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.size.x.max(self.size.y)
+    }
+
+    /// Tint to draw the sprite with while blinking.
+    pub fn tint(&self) -> Option<[f32; 3]> {
         if self.tinted {
-            sprite.draw_tinted(
-                context.transform,
-                graphics,
-                self.tint_rgb,
-            );
+            Some(self.tint_rgb)
         } else {
-            sprite.draw(
-                context.transform,
-                graphics
-            );
+            None
         }
+    }
 
+    /// `turn` is 1 for clockwise, -1 for counter clockwise and 0 to stop turning.
+    fn rotate(&self, body: &mut Body, turn: f64, delta: f64) {
+        let max_rot_vel = self.config.rotation_increment;
+        match self.config.angular_acceleration {
+            None => body.rot_vel = turn * max_rot_vel,
+            Some(acceleration) if turn != 0.0 => {
+                body.rot_vel = (body.rot_vel + turn * acceleration * delta).max(-max_rot_vel).min(max_rot_vel);
+            }
+            Some(acceleration) => {
+                let slowed = (body.rot_vel.abs() - acceleration * delta).max(0.0);
+                body.rot_vel = slowed * body.rot_vel.signum();
+            }
+        }
     }
 
-    fn rotate_cw(&mut self, delta: f64) {
-        self.rot += self.config.rotation_increment * delta;
+    fn accelerate(&self, transform: &Transform, body: &mut Body, thrust: f64, delta: f64) {
+        body.vel += angle_to_vector(thrust * delta, transform.rot - 90.0);
     }
-    fn rotate_ccw(&mut self, delta: f64) {
-        self.rot -= self.config.rotation_increment * delta;
+
+    fn brake(&self, body: &mut Body, delta: f64) {
+        let speed = (body.vel.length() - self.config.brake_increment * delta).max(0.0);
+        body.vel = body.vel.normalize() * speed;
     }
 
-    fn accelerate(&mut self, delta: f64) {
-        let rads = degree_to_radians(self.rot - 90.0);
-        let acceleration = Vector::new(
-            rads.cos() * self.config.thrust_increment * delta,
-            rads.sin() * self.config.thrust_increment * delta,
-        );
+    fn apply_drag(&self, body: &mut Body, delta: f64) {
+        body.vel *= (1.0 - self.config.drag * delta).max(0.0);
+    }
 
-        self.vel += acceleration;
-        self.vel = self.vel.min(self.config.max_velocity.into());
-        self.vel = self.vel.max((-1.0 * self.config.max_velocity).into());
+    fn cap_velocity(&self, body: &mut Body) {
+        if body.vel.length() > self.config.max_velocity {
+            body.vel = body.vel.normalize() * self.config.max_velocity;
+        }
     }
 
     fn blink(&mut self) {
@@ -114,23 +162,28 @@ impl Ship {
         self.blink_cooldown = self.config.blink_timer;
     }
 
-    pub fn reset_laser_cd(&mut self) {
-        self.laser_cooldown = self.config.laser_timer;
-    }
-
-    pub fn update(&mut self, args: UpdateArgs) {
-        self.pos += self.vel * args.dt.into() * 60.0.into();
-        self.pos = loop_pos(self.pos, self.radius, Vector::new(VIEW_W, VIEW_H));
-
-        if self.actions.rotate_cw {
-            self.rotate_cw(args.dt);
-        }
-        if self.actions.rotate_ccw {
-            self.rotate_ccw(args.dt);
+    /// Turns the held `actions` into changes to the ship's body. Moving the
+    /// ship is left to the physics system.
+    pub fn update(&mut self, transform: &Transform, body: &mut Body, args: UpdateArgs) {
+        let turn = match (self.actions.rotate_cw, self.actions.rotate_ccw) {
+            (true, false) => 1.0,
+            (false, true) => -1.0,
+            _ => 0.0,
+        };
+        self.rotate(body, turn, args.dt);
+        if self.boosters_enabled {
+            if self.actions.fire_boosters {
+                self.accelerate(transform, body, self.config.thrust_increment, args.dt);
+            }
+            if self.actions.reverse_thrust {
+                self.accelerate(transform, body, -self.config.reverse_thrust_increment, args.dt);
+            }
         }
-        if self.actions.fire_boosters {
-            self.accelerate(args.dt);
+        if self.actions.brake {
+            self.brake(body, args.dt);
         }
+        self.apply_drag(body, args.dt);
+        self.cap_velocity(body);
         if self.actions.is_blinking && self.blink_cooldown == 0.0 {
             self.blink();
             self.reset_blink_cd();
@@ -139,23 +192,21 @@ impl Ship {
         if self.blink_cooldown > 0.0 {
             self.blink_cooldown = (self.blink_cooldown - args.dt).max(0.0);
         }
+    }
 
-        if self.laser_cooldown > 0.0 {
-            self.laser_cooldown = (self.laser_cooldown - args.dt).max(0.0);
-        }
-    } 
-
-    pub fn is_firing_laser(&mut self) -> bool {
-        self.laser_cooldown == 0.0 && self.actions.is_shooting
+    pub fn get_laser_pos(&self, transform: &Transform) -> Vector {
+        angle_to_vector(self.radius() / 2.0, transform.rot - 90.0) + transform.pos
     }
-    
-    pub fn get_laser_pos(&mut self) -> Vector {
-        angle_to_vector(self.radius / 2.0, self.rot - 90.0) + self.pos
+
+    /// Outline of the sprite as drawn, for the debug overlay.
+    pub fn hull(&self, transform: &Transform) -> Vec<Vector> {
+        rotated_box(transform.pos, self.size, transform.rot)
     }
 
-    pub fn reset(&mut self) {
-        self.vel = Vector::new_empty();
-        self.pos = Vector::new(VIEW_W / 2.0, VIEW_H / 2.0);
+    pub fn reset(transform: &mut Transform, body: &mut Body) {
+        body.vel = Vector::new_empty();
+        body.rot_vel = 0.0;
+        transform.pos = Vector::new(VIEW_W / 2.0, VIEW_H / 2.0);
     }
 
     pub fn kill(&mut self) {
@@ -167,3 +218,82 @@ impl Ship {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Rig {
+        ship: Ship,
+        transform: Transform,
+        body: Body,
+    }
+
+    fn ship(extra: &str) -> Rig {
+        let config = format!(
+            "(scale: 0.5, rotation_increment: 300.0, thrust_increment: 10.0, blink_timer: 0.04, laser_timer: 0.08, max_velocity: 10.0, {})",
+            extra,
+        );
+        Rig {
+            ship: Ship::new(ron::de::from_str(&config).unwrap()),
+            transform: Transform::default(),
+            body: Body::default(),
+        }
+    }
+
+    fn tick(rig: &mut Rig) {
+        rig.ship.update(&rig.transform, &mut rig.body, UpdateArgs { dt: 1.0 / 60.0 });
+    }
+
+    #[test]
+    fn test_diagonal_speed_is_capped() {
+        let mut rig = ship("");
+        rig.transform.rot = 45.0;
+        rig.ship.actions.fire_boosters = true;
+        for _ in 0..600 {
+            tick(&mut rig);
+        }
+        assert!((rig.body.vel.length() - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_drag_slows_coasting_ship() {
+        let mut rig = ship("drag: 0.5");
+        rig.body.vel = Vector::new(5.0, 0.0);
+        for _ in 0..60 {
+            tick(&mut rig);
+        }
+        assert!(rig.body.vel.x < 5.0 * 0.62 && rig.body.vel.x > 0.0);
+    }
+
+    #[test]
+    fn test_angular_acceleration() {
+        let mut rig = ship("angular_acceleration: Some(600.0)");
+        rig.ship.actions.rotate_cw = true;
+        tick(&mut rig);
+        assert!(rig.body.rot_vel > 0.0 && rig.body.rot_vel < 300.0);
+        for _ in 0..60 {
+            tick(&mut rig);
+        }
+        assert_eq!(rig.body.rot_vel, 300.0);
+        rig.ship.actions.rotate_cw = false;
+        for _ in 0..60 {
+            tick(&mut rig);
+        }
+        assert_eq!(rig.body.rot_vel, 0.0);
+    }
+
+    #[test]
+    fn test_brake_and_reverse() {
+        let mut rig = ship("reverse_thrust_increment: 5.0, brake_increment: 30.0");
+        rig.ship.actions.reverse_thrust = true;
+        tick(&mut rig);
+        // the nose points up, so reversing moves the ship down
+        assert!(rig.body.vel.y > 0.0);
+        rig.ship.actions.reverse_thrust = false;
+        rig.ship.actions.brake = true;
+        for _ in 0..60 {
+            tick(&mut rig);
+        }
+        assert_eq!(rig.body.vel, Vector::new_empty());
+    }
+}
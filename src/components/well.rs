@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+use crate::components::{Collider, Transform};
+use crate::ecs::{Ecs, Entity};
+use crate::render::Renderer;
+use crate::utils::{Vector, wrapped_offset};
+use crate::{VIEW_H, VIEW_W};
+
+const WELL_RING: [f32; 4] = [0.6, 0.3, 1.0, 1.0];
+const HORIZON: [f32; 4] = [1.0, 0.5, 0.1, 1.0];
+const VOID: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+/// Pull is capped as if things were never closer than this, so it can't blow up.
+const MIN_DISTANCE: f64 = 20.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum WellKind {
+    /// Pulls things in and lets them slingshot past.
+    Gravity,
+    /// Pulls things in and swallows whatever crosses the event horizon.
+    BlackHole,
+}
+
+/// A well placed by a wave.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WellSpawn {
+    pub kind: WellKind,
+    pub pos: Vector,
+    /// Acceleration in px per 1/60s per second at a distance of 1px.
+    pub strength: f64,
+    /// Radius of a black hole's event horizon.
+    #[serde(default)]
+    pub horizon: f64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Well {
+    pub kind: WellKind,
+    pub strength: f64,
+    pub horizon: f64,
+}
+
+impl Well {
+    pub fn spawn(ecs: &mut Ecs, spawn: &WellSpawn) -> Entity {
+        let entity = ecs.spawn();
+        ecs.transforms.insert(entity, Transform { pos: spawn.pos, rot: 0.0 });
+        ecs.colliders.insert(entity, Collider { radius: spawn.horizon });
+        ecs.wells.insert(entity, Well {
+            kind: spawn.kind,
+            strength: spawn.strength,
+            horizon: spawn.horizon,
+        });
+        entity
+    }
+
+    /// Inverse square acceleration towards a well at `center` felt at `pos`.
+    pub fn pull(&self, center: Vector, pos: Vector) -> Vector {
+        let offset = wrapped_offset(pos, center, Vector::new(VIEW_W, VIEW_H));
+        let distance = offset.length().max(MIN_DISTANCE).max(self.horizon);
+        offset.normalize() * (self.strength / (distance * distance))
+    }
+
+    /// True when `pos` is inside the event horizon of a black hole at `center`.
+    pub fn swallows(&self, center: Vector, pos: Vector) -> bool {
+        self.kind == WellKind::BlackHole
+            && wrapped_offset(pos, center, Vector::new(VIEW_W, VIEW_H)).length() < self.horizon
+    }
+
+    pub fn draw<R: Renderer>(&self, transform: &Transform, renderer: &mut R) {
+        let pos = transform.pos;
+        match self.kind {
+            WellKind::Gravity => {
+                for i in 1..4 {
+                    let mut color = WELL_RING;
+                    color[3] = 1.0 / i as f32;
+                    renderer.ring(color, pos, 12.0 * f64::from(i), 2.0);
+                }
+            }
+            WellKind::BlackHole => {
+                renderer.disc(VOID, pos, self.horizon);
+                renderer.ring(HORIZON, pos, self.horizon, 3.0);
+            }
+        }
+    }
+}
+
+/// Sum of the pull of every well, given with its position, at `pos`.
+pub fn total_pull(wells: &[(Vector, Well)], pos: Vector) -> Vector {
+    wells.iter().fold(Vector::new_empty(), |sum, (center, well)| sum + well.pull(*center, pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CENTER: Vector = Vector { x: 500.0, y: 400.0 };
+
+    fn well(kind: WellKind) -> Well {
+        Well {
+            kind,
+            strength: 40000.0,
+            horizon: 25.0,
+        }
+    }
+
+    #[test]
+    fn test_pull_is_inverse_square() {
+        let well = well(WellKind::Gravity);
+        let near = well.pull(CENTER, Vector::new(400.0, 400.0));
+        let far = well.pull(CENTER, Vector::new(300.0, 400.0));
+        assert!(near.x > 0.0 && near.y == 0.0);
+        assert!((near.x / far.x - 4.0).abs() < 1e-9);
+        // capped inside the horizon instead of growing without bound
+        assert_eq!(well.pull(CENTER, Vector::new(499.0, 400.0)).length(), 40000.0 / 625.0);
+    }
+
+    #[test]
+    fn test_pull_wraps_across_edges() {
+        let well = well(WellKind::Gravity);
+        assert!(well.pull(Vector::new(10.0, 400.0), Vector::new(VIEW_W - 10.0, 400.0)).x > 0.0);
+    }
+
+    #[test]
+    fn test_only_black_holes_swallow() {
+        let inside = Vector::new(510.0, 400.0);
+        assert!(well(WellKind::BlackHole).swallows(CENTER, inside));
+        assert!(!well(WellKind::BlackHole).swallows(CENTER, Vector::new(530.0, 400.0)));
+        assert!(!well(WellKind::Gravity).swallows(CENTER, inside));
+    }
+}
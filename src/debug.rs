@@ -0,0 +1,68 @@
+use crate::render::Renderer;
+use crate::utils::Vector;
+use crate::world::World;
+
+const HITBOX: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+const VELOCITY: [f32; 4] = [1.0, 1.0, 0.0, 1.0];
+const LASER_POS: [f32; 4] = [1.0, 0.0, 1.0, 1.0];
+const STATS: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+
+// velocities are in px per 1/60s, scale them up so they are visible
+const VELOCITY_SCALE: f64 = 10.0;
+
+/// Hitboxes, velocity vectors and perf stats drawn on top of the playfield.
+#[derive(Default)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+}
+
+impl DebugOverlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn draw<R: Renderer>(&self, stats: &[String], world: &World, renderer: &mut R) {
+        if !self.enabled {
+            return;
+        }
+        let ecs = &world.ecs;
+        for (entity, collider) in ecs.colliders.iter() {
+            if let Some(transform) = ecs.transforms.get(entity) {
+                renderer.ring(HITBOX, transform.pos, collider.radius, 1.0);
+            }
+        }
+        for (entity, body) in ecs.bodies.iter() {
+            if let Some(transform) = ecs.transforms.get(entity) {
+                renderer.line(VELOCITY, transform.pos, transform.pos + body.vel * VELOCITY_SCALE, 1.0);
+            }
+        }
+
+        let ship = world.ship();
+        let transform = world.ship_transform();
+        let hull = ship.hull(transform);
+        for (i, &a) in hull.iter().enumerate() {
+            renderer.line(HITBOX, a, hull[(i + 1) % hull.len()], 1.0);
+        }
+        renderer.disc(LASER_POS, ship.get_laser_pos(transform), 2.0);
+
+        for (i, stat) in stats.iter().enumerate() {
+            renderer.text(STATS, 12, stat, Vector::new(50.0, 70.0 + i as f64 * 16.0));
+        }
+    }
+}
+
+/// Corners of a `size` box centred on `pos` and rotated by `rot` degrees.
+pub fn rotated_box(pos: Vector, size: Vector, rot: f64) -> Vec<Vector> {
+    let half = size / 2.0;
+    let corners = [
+        Vector::new(-half.x, -half.y),
+        Vector::new(half.x, -half.y),
+        Vector::new(half.x, half.y),
+        Vector::new(-half.x, half.y),
+    ];
+    corners.iter().map(|c| c.rotate(rot) + pos).collect()
+}
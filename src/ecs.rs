@@ -0,0 +1,241 @@
+use crate::components::{Body, Collider, Lifetime, Renderable, Transform};
+use crate::components::projectile::{Bounded, Missile, Projectile};
+use crate::components::roid::Roid;
+use crate::components::ship::Ship;
+use crate::components::well::Well;
+use crate::weapons::Arsenal;
+use serde::{Deserialize, Serialize};
+
+/// Handle to an entity. The generation changes every time an index is
+/// reused, so a handle to a despawned entity never finds its replacement.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    pub fn index(self) -> usize {
+        self.index as usize
+    }
+}
+
+/// Hands out entity handles and recycles the indices of despawned ones.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Entities {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+}
+
+impl Entities {
+    pub fn spawn(&mut self) -> Entity {
+        match self.free.pop() {
+            Some(index) => {
+                self.alive[index as usize] = true;
+                Entity {
+                    index,
+                    generation: self.generations[index as usize],
+                }
+            }
+            None => {
+                self.generations.push(0);
+                self.alive.push(true);
+                Entity {
+                    index: self.generations.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    /// Returns false if the entity was already gone.
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        self.alive[entity.index()] = false;
+        self.generations[entity.index()] += 1;
+        self.free.push(entity.index);
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.generations.get(entity.index()) == Some(&entity.generation) && self.alive[entity.index()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.generations.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// One component type for every entity, indexed by entity index.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Storage<T> {
+    items: Vec<Option<(u32, T)>>,
+    len: usize,
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> Storage<T> {
+    pub fn insert(&mut self, entity: Entity, component: T) {
+        if self.items.len() <= entity.index() {
+            self.items.resize_with(entity.index() + 1, || None);
+        }
+        if self.items[entity.index()].is_none() {
+            self.len += 1;
+        }
+        self.items[entity.index()] = Some((entity.generation, component));
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        let slot = self.items.get_mut(entity.index())?;
+        match slot {
+            Some((generation, _)) if *generation == entity.generation => {
+                self.len -= 1;
+                slot.take().map(|(_, component)| component)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        match self.items.get(entity.index()) {
+            Some(Some((generation, component))) if *generation == entity.generation => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        match self.items.get_mut(entity.index()) {
+            Some(Some((generation, component))) if *generation == entity.generation => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.items.iter().enumerate().filter_map(|(index, slot)| {
+            slot.as_ref().map(|(generation, component)| {
+                (Entity { index: index as u32, generation: *generation }, component)
+            })
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.items.iter_mut().enumerate().filter_map(|(index, slot)| {
+            slot.as_mut().map(|(generation, component)| {
+                (Entity { index: index as u32, generation: *generation }, component)
+            })
+        })
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.iter().map(|(entity, _)| entity)
+    }
+}
+
+/// Every entity and its components. Shared components describe how things
+/// move, collide and draw, the rest mark what kind of thing an entity is.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Ecs {
+    pub entities: Entities,
+    pub transforms: Storage<Transform>,
+    pub bodies: Storage<Body>,
+    pub colliders: Storage<Collider>,
+    pub renderables: Storage<Renderable>,
+    pub lifetimes: Storage<Lifetime>,
+    pub bounded: Storage<Bounded>,
+    pub arsenals: Storage<Arsenal>,
+    pub ships: Storage<Ship>,
+    pub projectiles: Storage<Projectile>,
+    pub missiles: Storage<Missile>,
+    pub roids: Storage<Roid>,
+    pub wells: Storage<Well>,
+}
+
+impl Ecs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn(&mut self) -> Entity {
+        self.entities.spawn()
+    }
+
+    /// Removes the entity and all of its components.
+    pub fn despawn(&mut self, entity: Entity) {
+        if !self.entities.despawn(entity) {
+            return;
+        }
+        self.transforms.remove(entity);
+        self.bodies.remove(entity);
+        self.colliders.remove(entity);
+        self.renderables.remove(entity);
+        self.lifetimes.remove(entity);
+        self.bounded.remove(entity);
+        self.arsenals.remove(entity);
+        self.ships.remove(entity);
+        self.projectiles.remove(entity);
+        self.missiles.remove(entity);
+        self.roids.remove(entity);
+        self.wells.remove(entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_handles_miss() {
+        let mut ecs = Ecs::new();
+        let first = ecs.spawn();
+        ecs.lifetimes.insert(first, Lifetime { remaining: 1.0 });
+        ecs.despawn(first);
+        let second = ecs.spawn();
+        assert_eq!(first.index(), second.index());
+        assert!(!ecs.entities.is_alive(first));
+        assert!(ecs.lifetimes.get(first).is_none());
+        ecs.lifetimes.insert(second, Lifetime { remaining: 2.0 });
+        assert!(ecs.lifetimes.get(first).is_none());
+        assert!(ecs.lifetimes.remove(first).is_none());
+        assert_eq!(ecs.lifetimes.len(), 1);
+    }
+
+    #[test]
+    fn test_iter_skips_empty_slots() {
+        let mut ecs = Ecs::new();
+        let entities: Vec<Entity> = (0..4).map(|_| ecs.spawn()).collect();
+        for (i, entity) in entities.iter().enumerate() {
+            ecs.lifetimes.insert(*entity, Lifetime { remaining: i as f64 });
+        }
+        ecs.despawn(entities[1]);
+        let left: Vec<Entity> = ecs.lifetimes.entities().collect();
+        assert_eq!(left, vec![entities[0], entities[2], entities[3]]);
+        assert_eq!(ecs.entities.len(), 3);
+    }
+}
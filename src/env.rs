@@ -0,0 +1,196 @@
+use piston_window::UpdateArgs;
+
+use crate::components::ship::Actions;
+use crate::utils::Vector;
+use crate::waves::Waves;
+use crate::world::World;
+use crate::{GameConfig, VIEW_H, VIEW_W};
+
+#[derive(Clone, Debug)]
+pub struct EnvOptions {
+    /// Roids included in each observation, nearest first, padded with zeroes.
+    pub nearest_roids: usize,
+    /// Simulated seconds per `step`.
+    pub dt: f64,
+    /// Subtracted from the reward on the step the ship dies.
+    pub death_penalty: f64,
+    /// Steps before an episode is cut off, 0 for no limit.
+    pub max_steps: u64,
+}
+
+impl Default for EnvOptions {
+    fn default() -> Self {
+        Self {
+            nearest_roids: 8,
+            dt: 1.0 / 60.0,
+            death_penalty: 100.0,
+            max_steps: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShipObservation {
+    /// Position normalised to 0..1 over the playfield.
+    pub pos: Vector,
+    pub vel: Vector,
+    /// Heading as `(sin, cos)` so it doesn't jump at 360.
+    pub heading: Vector,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoidObservation {
+    /// Offset from the ship on the wrapping playfield.
+    pub offset: Vector,
+    /// Velocity relative to the ship.
+    pub vel: Vector,
+    pub diameter: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Observation {
+    pub ship: ShipObservation,
+    pub roids: Vec<RoidObservation>,
+}
+
+impl Observation {
+    /// Flat feature vector, `6 + 5 * nearest_roids` long.
+    pub fn to_vec(&self, nearest_roids: usize) -> Vec<f64> {
+        let ship = &self.ship;
+        let mut v = vec![ship.pos.x, ship.pos.y, ship.vel.x, ship.vel.y, ship.heading.x, ship.heading.y];
+        for i in 0..nearest_roids {
+            match self.roids.get(i) {
+                Some(roid) => v.extend_from_slice(&[roid.offset.x, roid.offset.y, roid.vel.x, roid.vel.y, roid.diameter]),
+                None => v.extend_from_slice(&[0.0; 5]),
+            }
+        }
+        v
+    }
+}
+
+/// Gym style wrapper around a headless `World` for training agents.
+pub struct Env {
+    config: GameConfig,
+    waves: Waves,
+    options: EnvOptions,
+    world: World,
+    steps: u64,
+}
+
+impl Env {
+    pub fn new(config: GameConfig, waves: Waves, options: EnvOptions) -> Self {
+        let world = World::new(config.clone(), waves.clone(), 0);
+        Self {
+            config,
+            waves,
+            options,
+            world,
+            steps: 0,
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.world = World::new(self.config.clone(), self.waves.clone(), seed);
+        self.steps = 0;
+        self.observe()
+    }
+
+    /// Advances one tick with `action` held. The reward is the points scored
+    /// this tick, less `death_penalty` if the ship died, which ends the episode.
+    pub fn step(&mut self, action: Actions) -> (Observation, f64, bool) {
+        self.world.ship_mut().actions.take_controls(action);
+        let tick = self.world.update(UpdateArgs { dt: self.options.dt });
+        self.steps += 1;
+        let mut reward = f64::from(tick.points);
+        if tick.ship_died {
+            reward -= self.options.death_penalty;
+        }
+        let timed_out = self.options.max_steps > 0 && self.steps >= self.options.max_steps;
+        (self.observe(), reward, tick.ship_died || timed_out)
+    }
+
+    pub fn observe(&self) -> Observation {
+        let perception = self.world.perception();
+        let me = perception.me;
+        let heading = (me.rot - 90.0).to_radians();
+        let mut roids: Vec<RoidObservation> = perception
+            .threats
+            .iter()
+            .map(|threat| RoidObservation {
+                offset: perception.offset_to(threat.pos),
+                vel: threat.vel - me.vel,
+                diameter: threat.radius * 2.0,
+            })
+            .collect();
+        roids.sort_by(|a, b| a.offset.length().partial_cmp(&b.offset.length()).unwrap());
+        roids.truncate(self.options.nearest_roids);
+        Observation {
+            ship: ShipObservation {
+                pos: Vector::new(me.pos.x / VIEW_W, me.pos.y / VIEW_H),
+                vel: me.vel,
+                heading: Vector::new(heading.sin(), heading.cos()),
+            },
+            roids,
+        }
+    }
+}
+
+/// Maps a discrete action in `0..16` onto `Actions`, one bit per control.
+pub fn action_from_index(index: usize) -> Actions {
+    Actions {
+        rotate_cw: index & 1 != 0,
+        rotate_ccw: index & 2 != 0,
+        fire_boosters: index & 4 != 0,
+        is_shooting: index & 8 != 0,
+        ..Actions::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::Assets;
+    use crate::{load_config, load_waves};
+
+    fn env() -> Env {
+        let assets = Assets::new(None);
+        let config = load_config(None, &assets).unwrap();
+        let waves = load_waves(None, &assets).unwrap();
+        Env::new(config, waves, EnvOptions::default())
+    }
+
+    #[test]
+    fn test_reset_is_deterministic() {
+        let mut env = env();
+        let first = env.reset(42);
+        for i in 0..120 {
+            env.step(action_from_index(i % 16));
+        }
+        assert_eq!(env.reset(42), first);
+        assert_ne!(env.reset(43), first);
+    }
+
+    #[test]
+    fn test_observation_is_padded() {
+        let mut env = env();
+        let obs = env.reset(7);
+        assert_eq!(obs.to_vec(8).len(), 6 + 5 * 8);
+        assert_eq!(obs.to_vec(20).len(), 6 + 5 * 20);
+    }
+
+    #[test]
+    fn test_steps_are_deterministic() {
+        let mut a = env();
+        let mut b = env();
+        a.reset(3);
+        b.reset(3);
+        for i in 0..300 {
+            let action = action_from_index((i * 7) % 16);
+            assert_eq!(a.step(action_from_index((i * 7) % 16)), b.step(action));
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use crate::components::roid::RoidSize;
+use crate::stats::SessionStats;
+use crate::utils::Vector;
+
+/// What killed the ship.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Death {
+    Roid,
+    BlackHole,
+    OutOfTime,
+}
+
+/// Something that happened in the simulation. The world publishes these as
+/// they happen, keeps its own scoring and stats up to date from them, and
+/// holds on to them until the end of the tick for everyone else to drain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameEvent {
+    /// A shot left the ship at `pos` heading `rot` degrees.
+    LaserFired { pos: Vector, rot: f64 },
+    /// A shot went away without hitting anything.
+    ShotMissed,
+    /// A shot hit a roid at `pos`, `first_hit` unless it already went through another one.
+    RoidHit { pos: Vector, first_hit: bool },
+    RoidDestroyed { size: RoidSize, pos: Vector, diameter: f64 },
+    ShipKilled { pos: Vector, death: Death },
+    /// Wave `level` was cleared after `wave_time` seconds.
+    WaveCleared { level: u32, wave_time: f64 },
+    /// Follows whatever event scored the points that earned it.
+    ExtraLife { lives: u32 },
+    /// The last life was lost, with the stats for the game that ended.
+    GameOver { stats: SessionStats },
+}
+
+/// Something that reacts to the events of a tick.
+pub trait Subscriber {
+    fn notify(&mut self, event: &GameEvent);
+}
+
+/// Prints what happened to the ship and the waves.
+pub struct EventLog;
+
+impl Subscriber for EventLog {
+    fn notify(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::ShipKilled { death: Death::Roid, .. } => println!("killing the ship"),
+            GameEvent::ShipKilled { death: Death::BlackHole, .. } => {
+                println!("swallowed by a black hole, killing the ship")
+            }
+            GameEvent::ShipKilled { death: Death::OutOfTime, .. } => println!("out of time, killing the ship"),
+            GameEvent::WaveCleared { level, wave_time } => println!("cleared wave {} in {:.1}s", level, wave_time),
+            GameEvent::ExtraLife { lives } => println!("extra life, {} left", lives),
+            GameEvent::GameOver { stats } => println!("game over with {} points", stats.score),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use piston_window::UpdateArgs;
+    use crate::assets::Assets;
+    use crate::world::World;
+    use crate::{load_config, load_waves};
+
+    /// The world with a single roid left, still, `offset` away from the ship.
+    fn world_with_roid(offset: Vector) -> World {
+        let assets = Assets::new(None);
+        let mut world = World::new(load_config(None, &assets).unwrap(), load_waves(None, &assets).unwrap(), 4);
+        let ship_pos = world.ecs.transforms.get(world.ship).unwrap().pos;
+        let roids: Vec<_> = world.ecs.roids.entities().collect();
+        for &roid in &roids[1..] {
+            world.ecs.despawn(roid);
+        }
+        world.ecs.transforms.get_mut(roids[0]).unwrap().pos = ship_pos + offset;
+        let body = world.ecs.bodies.get_mut(roids[0]).unwrap();
+        body.vel = Vector::new_empty();
+        body.rot_vel = 0.0;
+        world
+    }
+
+    fn play(world: &mut World, ticks: u32) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        for _ in 0..ticks {
+            world.update(UpdateArgs { dt: 1.0 / 60.0 });
+            events.extend(world.drain_events());
+        }
+        events
+    }
+
+    #[test]
+    fn test_shooting_a_roid_publishes_in_order() {
+        let mut world = world_with_roid(Vector::new(0.0, -200.0));
+        world.ship_mut().actions.is_shooting = true;
+        let events = play(&mut world, 120);
+        let first = |wanted: fn(&GameEvent) -> bool| events.iter().position(wanted).unwrap();
+        let fired = first(|event| matches!(event, GameEvent::LaserFired { .. }));
+        let hit = first(|event| matches!(event, GameEvent::RoidHit { first_hit: true, .. }));
+        let destroyed = first(|event| matches!(event, GameEvent::RoidDestroyed { .. }));
+        assert!(fired < hit && hit < destroyed);
+        let shots = events.iter().filter(|event| matches!(event, GameEvent::LaserFired { .. })).count();
+        assert_eq!(shots as u32, world.stats.shots_fired);
+        assert!(world.drain_events().next().is_none());
+    }
+
+    #[test]
+    fn test_ship_killed_by_a_roid() {
+        let mut world = world_with_roid(Vector::new(5.0, 0.0));
+        let events = play(&mut world, 1);
+        assert!(matches!(events[..], [GameEvent::ShipKilled { death: Death::Roid, .. }]));
+        assert_eq!(world.stats.deaths, 1);
+    }
+}
@@ -1,223 +1,543 @@
-use piston_window::{PistonWindow, clear, RenderEvent, UpdateEvent, PressEvent, ReleaseEvent, Button, Key, TextureSettings, text, Transformed};
-use opengl_graphics::{GlGraphics, Texture, GlyphCache};
-use sprite::{Scene};
+use piston_window::{PistonWindow, RenderEvent, UpdateEvent, UpdateArgs, PressEvent, ReleaseEvent, Button, Key};
+use opengl_graphics::GlGraphics;
 use serde::Deserialize;
-use std::{rc::Rc, time::SystemTime};
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 
-use crate::utils::{BLACK, point_within_radius};
-use crate::GameConfig;
-use crate::components::ship::Ship;
-use crate::components::laser::Laser;
-use crate::components::roid::{Roid, RoidConfig};
-use rand::rngs::ThreadRng;
-use rand::{thread_rng};
-use std::path::PathBuf;
+use crate::utils::Vector;
+use crate::assets::Assets;
+use crate::ai::Autopilot;
+use crate::audio::{Audio, Sound};
+use crate::components::ship::Actions;
+use crate::debug::DebugOverlay;
+use crate::events::{EventLog, GameEvent, Subscriber};
+use crate::particles::Particles;
+use crate::profiler::{Profiler, Phase};
+use crate::render::{self, Renderer};
+use crate::render::gl::GlRenderer;
+use crate::render::term;
+use crate::replay::Replay;
+use crate::rewind::Rewind;
+use crate::scoring::ScoreEvent;
+use crate::snapshot::Snapshot;
+use crate::stats::{Records, SessionStats};
+use crate::tui::{self, HeldKeys, Input, Terminal};
+use crate::world::{Tick, World};
+use crate::waves::Waves;
+use crate::{GameConfig, VIEW_H, VIEW_W};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 pub struct KeyConfig {
     rotate_cw: Key,
     rotate_ccw: Key,
     fire_laser: Key,
     thrust: Key,
+    #[serde(default = "default_reverse_thrust")]
+    reverse_thrust: Key,
+    #[serde(default = "default_brake")]
+    brake: Key,
+    #[serde(default = "default_cycle_weapon")]
+    cycle_weapon: Key,
+    #[serde(default = "default_toggle_debug")]
+    toggle_debug: Key,
+    #[serde(default = "default_quicksave")]
+    quicksave: Key,
+    #[serde(default = "default_quickload")]
+    quickload: Key,
+    #[serde(default = "default_rewind")]
+    rewind: Key,
 }
 
-#[derive(Deserialize)]
-pub struct GeneratorConfig {
-    num_of_asteroids: i32,
+fn default_reverse_thrust() -> Key {
+    Key::X
 }
 
-pub struct Game {
-    lasers: Vec<Laser>,
-    roids: Vec<Roid>,
+fn default_brake() -> Key {
+    Key::LShift
 }
 
-struct TickCounter {
-    input: u64,
-    render: u64,
-    update: u64,
-    last_tick: SystemTime,
-    first_frame: SystemTime,
-    render_frames: f64,
-    avg_render_frames: f64,
-    update_ticks: f64,
+fn default_cycle_weapon() -> Key {
+    Key::Q
 }
 
-impl TickCounter {
-    fn new(input: u64, render: u64, update: u64) -> Self{
-        let now = SystemTime::now();
-        Self {
-            input, render, update,
-            last_tick: now,
-            first_frame: now,
-            render_frames: 0.0,
-            avg_render_frames: 0.0,
-            update_ticks: 0.0,
-            avg_update_ticks: 0.0,
+fn default_toggle_debug() -> Key {
+    Key::F3
+}
+
+fn default_quicksave() -> Key {
+    Key::F5
+}
+
+fn default_quickload() -> Key {
+    Key::F9
+}
+
+fn default_rewind() -> Key {
+    Key::Backspace
+}
+
+impl KeyConfig {
+    /// These keys with a brake bound to a modifier, which a terminal can't
+    /// report on its own, moved to `TERMINAL_BRAKE`.
+    fn for_terminal(&self) -> Self {
+        let mut keys = self.clone();
+        if !tui::can_press(keys.brake) {
+            keys.brake = TERMINAL_BRAKE;
         }
+        keys
     }
-    fn tick_input(&mut self) {
-        self.input += 1;
-    }
-    fn tick_render(&mut self) {
-        self.render += 1;
-    }
-    fn tick_update(&mut self) {
-        self.update += 1;
-    }
-    fn run(&mut self) {
-        let delta_last = self.last_tick.elapsed().unwrap();
-        let delta_total = self.first_frame.elapsed().unwrap();
-        if delta_last.as_secs() == 0 {
-            return
+
+    /// Holds or lets go of the ship control `key` is bound to, if it is bound to one.
+    fn hold(&self, key: Key, actions: &mut Actions, held: bool) {
+        if key == self.rotate_cw {
+            actions.rotate_cw = held;
+        }
+        if key == self.rotate_ccw {
+            actions.rotate_ccw = held;
+        }
+        if key == self.thrust {
+            actions.fire_boosters = held;
+        }
+        if key == self.reverse_thrust {
+            actions.reverse_thrust = held;
+        }
+        if key == self.brake {
+            actions.brake = held;
+        }
+        if key == self.fire_laser {
+            actions.is_shooting = held;
         }
-        self.render_frames = delta_last.as_secs_f64();
-        self.avg_render_frames = self.render as f64 / delta_total.as_secs_f64();
-        self.last_tick = SystemTime::now();
-        self.update_ticks = self.update as f64 / delta_total.as_secs_f64();
     }
+}
+
+/// Seconds a scoring event stays on the HUD.
+const FEED_TIME: f64 = 2.0;
+/// Seconds the game over screen stays up unless a key dismisses it.
+const GAME_OVER_TIME: f64 = 8.0;
+/// Time between frames in the terminal.
+const TERMINAL_FRAME: Duration = Duration::from_millis(33);
+/// Brake key in the terminal when the configured one can't be pressed there.
+const TERMINAL_BRAKE: Key = Key::B;
+/// Longest tick the terminal takes, so a stalled connection doesn't jump the world ahead.
+const TERMINAL_MAX_DT: f64 = 0.1;
 
-    fn formatted(&self) -> String {
-        format!("FPS: {} TPS: {} ELP: {}", self.avg_render_frames, self.update_ticks, self.first_frame.elapsed().unwrap().as_secs_f64().floor())
+pub struct Game {
+    profiler: Profiler,
+    autopilot: Option<Autopilot>,
+    records: Records,
+    /// Where the quicksave key writes to and the quickload key reads from.
+    quicksave: PathBuf,
+    resume: Option<Snapshot>,
+    /// Where the session is saved as a replay when the game is closed.
+    record: Option<PathBuf>,
+    /// Sounds bound to a file, which the window plays.
+    sounds: Vec<Sound>,
+}
+
+/// Shown when the last life is lost, the world waits until it goes away.
+struct GameOver {
+    lines: Vec<String>,
+    remaining: f64,
+}
+
+impl GameOver {
+    /// Records the finished game and lists its stats and anything it unlocked.
+    fn new(records: &mut Records, stats: &SessionStats) -> Self {
+        let mut lines = vec!["GAME OVER".to_string()];
+        lines.extend(stats.lines());
+        for achievement in records.finish_game(stats) {
+            lines.push(format!("Unlocked {}: {}", achievement.name, achievement.description));
+        }
+        lines.push(format!("Best score: {}", records.lifetime.best_score));
+        Self { lines, remaining: GAME_OVER_TIME }
     }
 }
 
 impl Game {
-    pub fn new() -> Self {
+    pub fn new(profiler: Profiler, autopilot: Option<Autopilot>, records: Records, quicksave: PathBuf) -> Self {
         Self {
-            lasers: Vec::new(),
-            roids: Vec::new(),
+            profiler,
+            autopilot,
+            records,
+            quicksave,
+            resume: None,
+            record: None,
+            sounds: Vec::new(),
         }
     }
 
-    pub fn run(&mut self, window: &mut PistonWindow, opengl: &mut GlGraphics, scene: &mut Scene<Texture>, config: GameConfig, config_dir: PathBuf) {
-        let mut rng = thread_rng();
-        let mut level = 1;
-        let mut glyph_cache = GlyphCache::new("./assets/fonts/square.ttf", (), TextureSettings::new()).unwrap();
-        let texture = load_texture(config_dir);
-        let mut ship = Ship::new(config.ship_config.clone(), scene, texture.clone());
-        let mut roids = generate_roids(scene, texture.clone(), &mut rng, config.generator_config.num_of_asteroids, config.roid_config.clone());
-        let mut lasers: Vec<Laser> = Vec::new();
-        let mut tick_counter = TickCounter::new(0, 0, 0);
-        let mut score = 0;
+    /// Starts `run` from `snapshot` instead of the first wave.
+    pub fn resume(&mut self, snapshot: Snapshot) {
+        self.resume = Some(snapshot);
+    }
+
+    /// Records the session, saving it as a replay to `path` when the game is closed.
+    pub fn record(&mut self, path: PathBuf) {
+        self.record = Some(path);
+    }
+
+    /// Plays `sounds` in the window when they come up, they have to be bound
+    /// by `audio::with_sounds` already.
+    pub fn play_sounds(&mut self, sounds: Vec<Sound>) {
+        self.sounds = sounds;
+    }
+
+    /// Plays in `window`, failing only when the art can't be loaded.
+    pub fn run(
+        &mut self, window: &mut PistonWindow, opengl: &mut GlGraphics, config: GameConfig, waves: Waves, assets: &Assets,
+    ) -> Result<(), String> {
+        let mut glyph_cache = assets.glyph_cache()?;
+        let texture = assets.ship_texture()?;
+        let profiler = &mut self.profiler;
+        let keys = config.key_config.clone();
+        let mut rewind = Rewind::new(config.rewind_seconds);
+        let mut rewinding = false;
+        let mut world = World::new(config, waves, rand::random());
+        if let Some(snapshot) = self.resume.take() {
+            world.restore(snapshot);
+        }
+        let mut replay = self.record.as_ref().map(|_| Replay::new(world.snapshot()));
+        let mut debug = DebugOverlay::new();
+        let mut log = EventLog;
+        let mut particles = Particles::new();
+        let mut audio = Audio::new();
+        let mut feed: Vec<(ScoreEvent, f64)> = Vec::new();
+        // what the last quicksave or quickload did and how long it stays up
+        let mut message: Option<(String, f64)> = None;
+        let mut game_over: Option<GameOver> = None;
         while let Some(event) = window.next() {
             if let Some(args) = event.render_args() {
+                let render_start = Instant::now();
+                let stats = if debug.enabled {
+                    let ecs = &world.ecs;
+                    let mut stats = profiler.formatted();
+                    stats.push(format!(
+                        "Entities: {} Shots: {} Roids: {} Wells: {}",
+                        ecs.entities.len(), ecs.projectiles.len(), ecs.roids.len(), ecs.wells.len(),
+                    ));
+                    stats
+                } else {
+                    Vec::new()
+                };
                 opengl.draw(args.viewport(), |context, graphics| {
-                    clear(BLACK,graphics);
-                    ship.draw(context, graphics, scene);
-                    for laser in &mut lasers {
-                        laser.draw(context, graphics, scene);
+                    let renderer = &mut GlRenderer::new(context, graphics, &texture, &mut glyph_cache);
+                    render::draw_frame(renderer, &world, &debug, &stats);
+                    particles.draw(renderer);
+                    if rewinding {
+                        let status = format!("<< REWIND {:.1}s", rewind.seconds());
+                        renderer.text([0.6, 0.8, 1.0, 1.0], 16, &status, Vector::new(VIEW_W - 200.0, 50.0));
+                    }
+                    // the feed starts below the debug stats when they're up
+                    for (i, (event, _)) in feed.iter().enumerate() {
+                        let at = Vector::new(50.0, 70.0 + 16.0 * (stats.len() + i) as f64);
+                        renderer.text([1.0, 1.0, 0.6, 1.0], 12, &event.to_string(), at);
                     }
-                    for roid in &mut roids {
-                        roid.draw(context, graphics, scene);
+                    if let Some((text, _)) = &message {
+                        renderer.text([0.6, 0.8, 1.0, 1.0], 14, text, Vector::new(50.0, VIEW_H - 30.0));
+                    }
+                    if let Some(game_over) = &game_over {
+                        for (i, line) in game_over.lines.iter().enumerate() {
+                            let at = Vector::new(VIEW_W / 2.0 - 200.0, VIEW_H / 3.0 + 24.0 * i as f64);
+                            renderer.text([1.0; 4], 16, line, at);
+                        }
                     }
-                    text([1.0; 4], 14, &format!("Targets Remaining: {} Score: {}", roids.len(), score), &mut glyph_cache, context.transform.trans(50.0, 50.0), graphics);
                 });
-                tick_counter.tick_render();
+                profiler.record(Phase::Render, render_start);
+                profiler.end_frame();
             }
             if let Some(args) = event.update_args() {
-                ship.update(args);
-                if ship.is_firing_laser() {
-                    lasers.push(Laser::new(
-                        ship.vel.clone(),
-                        config.laser_config.clone(),
-                        ship.get_laser_pos(),
-                        ship.rot.clone(),
-                        texture.clone(),
-                        scene,
-                    ));
-                    ship.reset_laser_cd();
+                profiler.tick_update();
+                particles.update(args.dt);
+                if let Some((_, remaining)) = &mut message {
+                    *remaining -= args.dt;
                 }
-
-                for laser in &mut lasers {
-                    laser.update(args);
+                message = message.filter(|(_, remaining)| *remaining > 0.0);
+            }
+            if let Some(args) = event.update_args().filter(|_| rewinding && game_over.is_none()) {
+                if let Some((snapshot, ticks)) = rewind.rewind(args.dt) {
+                    restore(&mut world, snapshot);
+                    if let Some(replay) = &mut replay {
+                        replay.truncate(ticks);
+                    }
                 }
-                // loop a
-                for roid in &mut roids {
-                    roid.update(args);
+            } else if let Some(args) = event.update_args().filter(|_| game_over.is_none()) {
+                rewind.record(args.dt, (world.snapshot(), replay.as_ref().map_or(0, Replay::len)));
+                let movement_start = Instant::now();
+                if let Some(autopilot) = &mut self.autopilot {
+                    let perception = world.perception();
+                    world.ship_mut().actions.take_controls(autopilot.update(args, &perception));
                 }
-                //loop
-                roids.retain(|roid| {
-                    let mut keep_asteroid = true;
-                    //loop a1
-                    lasers.retain(|l| {
-                        if point_within_radius(l.pos, roid.pos, roid.diameter / 2.0) {
-                            keep_asteroid = false;
-                            score += roid.diameter.round() as u32;
-                        }
-                        l.life > 0.0 && keep_asteroid
-                    });
-                    if point_within_radius(ship.pos, roid.pos, roid.diameter / 2.0 + ship.radius) {
-                        ship.kill();
-                        println!("killing the ship");
-                    }
-                    keep_asteroid
+                if let Some(replay) = &mut replay {
+                    replay.record(args.dt, &world.ship().actions);
+                }
+                world.move_entities(args);
+                profiler.record(Phase::Movement, movement_start);
+                let mut tick = Tick::default();
+                let collision_start = Instant::now();
+                world.collide(&mut tick);
+                profiler.record(Phase::Collision, collision_start);
+                let spawning_start = Instant::now();
+                world.spawn(&mut tick);
+                profiler.record(Phase::Spawning, spawning_start);
+                feed.retain_mut(|(_, remaining)| {
+                    *remaining -= args.dt;
+                    *remaining > 0.0
                 });
-                tick_counter.tick_update();
-                if roids.len() == 0 && !ship.dead {
-                    ship.reset();
-                    level += 1;
-                    let num_of_roids = config.generator_config.num_of_asteroids + level * level;
-                    roids = generate_roids(scene, texture.clone(), &mut rng, num_of_roids, config.roid_config.clone());
-                } else if ship.dead {
-                    ship.reset();
-                    level = 1;
-                    roids = generate_roids(scene, texture.clone(), &mut rng, config.generator_config.num_of_asteroids, config.roid_config.clone());
-                    score = 0;
-                    ship.unkill();
+                feed.extend(world.scoring.events().iter().map(|&event| (event, FEED_TIME)));
+                for event in world.drain_events() {
+                    log.notify(&event);
+                    particles.notify(&event);
+                    audio.notify(&event);
+                    if let GameEvent::GameOver { stats } = &event {
+                        game_over = Some(GameOver::new(&mut self.records, stats));
+                    }
+                }
+                audio.play(&self.sounds, args.dt);
+            } else if let Some(args) = event.update_args() {
+                let screen = game_over.as_mut().unwrap();
+                screen.remaining -= args.dt;
+                if screen.remaining <= 0.0 {
+                    game_over = None;
                 }
             }
+            let input_start = Instant::now();
             if let Some(Button::Keyboard(key)) = event.press_args() {
-                if key == config.key_config.rotate_cw {
-                    ship.actions.rotate_cw  = true;
+                game_over = None;
+                keys.hold(key, &mut world.ship_mut().actions, true);
+                if key == keys.cycle_weapon {
+                    world.cycle_weapon();
+                    if let Some(replay) = &mut replay {
+                        replay.cycle_weapon();
+                    }
+                }
+                if key == keys.toggle_debug {
+                    debug.toggle();
                 }
-                if key == config.key_config.rotate_ccw {
-                    ship.actions.rotate_ccw = true;
+                if key == keys.rewind {
+                    rewinding = true;
                 }
-                if key == config.key_config.thrust {
-                    ship.actions.fire_boosters = true;
+                if key == keys.quicksave {
+                    let text = match world.snapshot().save(&self.quicksave) {
+                        Ok(()) => format!("saved to {}", self.quicksave.display()),
+                        Err(e) => format!("Failed to quicksave: {}", e),
+                    };
+                    message = Some((text, FEED_TIME));
                 }
-                if key == config.key_config.fire_laser {
-                    ship.actions.is_shooting = true;
+                if key == keys.quickload {
+                    match Snapshot::load(&self.quicksave) {
+                        Ok(snapshot) => {
+                            restore(&mut world, snapshot);
+                            rewind.clear();
+                            if replay.is_some() {
+                                replay = Some(Replay::new(world.snapshot()));
+                            }
+                            feed.clear();
+                            message = Some((format!("loaded {}", self.quicksave.display()), FEED_TIME));
+                        }
+                        Err(e) => message = Some((format!("Failed to quickload: {}", e), FEED_TIME)),
+                    }
                 }
-                tick_counter.tick_input();
             }
 
             if let Some(Button::Keyboard(key)) = event.release_args() {
-                if key == config.key_config.rotate_cw {
-                    ship.actions.rotate_cw  = false;
+                keys.hold(key, &mut world.ship_mut().actions, false);
+                if key == keys.rewind {
+                    rewinding = false;
                 }
-                if key == config.key_config.rotate_ccw {
-                    ship.actions.rotate_ccw = false;
+            }
+            profiler.record(Phase::Input, input_start);
+        }
+        profiler.flush();
+        self.save_replay(replay.as_ref());
+        Ok(())
+    }
+
+    /// Plays in the terminal instead of a window, drawing the playfield as
+    /// text, so the game works over SSH. Esc or Ctrl-C quits. A brake bound
+    /// to a modifier is on B instead and there's no debug overlay.
+    pub fn run_terminal(&mut self, config: GameConfig, waves: Waves) -> io::Result<()> {
+        let config_brake = config.key_config.brake;
+        let keys = config.key_config.for_terminal();
+        let mut rewind = Rewind::new(config.rewind_seconds);
+        let mut rewinding = false;
+        let mut world = World::new(config, waves, rand::random());
+        if let Some(snapshot) = self.resume.take() {
+            world.restore(snapshot);
+        }
+        let mut replay = self.record.as_ref().map(|_| Replay::new(world.snapshot()));
+        let mut terminal = Terminal::new()?;
+        let mut held = HeldKeys::new(terminal.reports_releases());
+        let mut particles = Particles::new();
+        let mut audio = Audio::new();
+        let mut feed: Vec<(ScoreEvent, f64)> = Vec::new();
+        let mut game_over: Option<GameOver> = None;
+        let mut message = if keys.brake == config_brake {
+            String::new()
+        } else {
+            format!("{:?} can't be read from a terminal, brake with {:?}", config_brake, keys.brake)
+        };
+        let mut last_frame = Instant::now();
+        loop {
+            let mut pressed = Vec::new();
+            let mut released = Vec::new();
+            for input in terminal.inputs(last_frame + TERMINAL_FRAME)? {
+                match input {
+                    Input::Quit => {
+                        // put the terminal back first so errors can be seen
+                        drop(terminal);
+                        self.save_replay(replay.as_ref());
+                        return Ok(());
+                    }
+                    Input::Press(key) if held.press(key) => pressed.push(key),
+                    Input::Release(key) if held.release(key) => released.push(key),
+                    _ => {}
+                }
+            }
+            let args = UpdateArgs { dt: last_frame.elapsed().as_secs_f64().min(TERMINAL_MAX_DT) };
+            last_frame = Instant::now();
+            released.extend(held.update(args.dt));
+            particles.update(args.dt);
+
+            for key in pressed {
+                game_over = None;
+                keys.hold(key, &mut world.ship_mut().actions, true);
+                if key == keys.cycle_weapon {
+                    world.cycle_weapon();
+                    if let Some(replay) = &mut replay {
+                        replay.cycle_weapon();
+                    }
                 }
-                if key == config.key_config.thrust {
-                    ship.actions.fire_boosters = false;
+                if key == keys.rewind {
+                    rewinding = true;
                 }
-                if key == config.key_config.fire_laser {
-                    ship.actions.is_shooting = false;
+                if key == keys.quicksave {
+                    message = match world.snapshot().save(&self.quicksave) {
+                        Ok(()) => format!("saved to {}", self.quicksave.display()),
+                        Err(e) => format!("Failed to quicksave: {}", e),
+                    };
+                }
+                if key == keys.quickload {
+                    match Snapshot::load(&self.quicksave) {
+                        Ok(snapshot) => {
+                            restore(&mut world, snapshot);
+                            rewind.clear();
+                            if replay.is_some() {
+                                replay = Some(Replay::new(world.snapshot()));
+                            }
+                            feed.clear();
+                            message.clear();
+                        }
+                        Err(e) => message = format!("Failed to quickload: {}", e),
+                    }
                 }
             }
-            tick_counter.run();
+            for key in released {
+                keys.hold(key, &mut world.ship_mut().actions, false);
+                if key == keys.rewind {
+                    rewinding = false;
+                }
+            }
+
+            if let Some(screen) = &mut game_over {
+                screen.remaining -= args.dt;
+                if screen.remaining <= 0.0 {
+                    game_over = None;
+                }
+            } else if rewinding {
+                if let Some((snapshot, ticks)) = rewind.rewind(args.dt) {
+                    restore(&mut world, snapshot);
+                    if let Some(replay) = &mut replay {
+                        replay.truncate(ticks);
+                    }
+                }
+            } else {
+                rewind.record(args.dt, (world.snapshot(), replay.as_ref().map_or(0, Replay::len)));
+                if let Some(autopilot) = &mut self.autopilot {
+                    let perception = world.perception();
+                    world.ship_mut().actions.take_controls(autopilot.update(args, &perception));
+                }
+                if let Some(replay) = &mut replay {
+                    replay.record(args.dt, &world.ship().actions);
+                }
+                world.update(args);
+                feed.retain_mut(|(_, remaining)| {
+                    *remaining -= args.dt;
+                    *remaining > 0.0
+                });
+                feed.extend(world.scoring.events().iter().map(|&event| (event, FEED_TIME)));
+                for event in world.drain_events() {
+                    particles.notify(&event);
+                    audio.notify(&event);
+                    if let GameEvent::GameOver { stats } = &event {
+                        game_over = Some(GameOver::new(&mut self.records, stats));
+                    }
+                }
+                if audio.drain().any(|sound| matches!(sound, Sound::ShipDeath | Sound::ExtraLife | Sound::GameOver)) {
+                    terminal.bell()?;
+                }
+            }
+
+            // the border, the HUD and the status line take two rows and columns each
+            let (cols, rows) = terminal.size()?;
+            let mut frame = term::draw_world(&world, cols.saturating_sub(2), rows.saturating_sub(4));
+            for pos in particles.positions() {
+                frame.put(pos, '.');
+            }
+            if let Some(screen) = &game_over {
+                for (i, line) in screen.lines.iter().enumerate() {
+                    frame.text(rows / 4 + i, line);
+                }
+            }
+            let mut lines = frame.lines();
+            lines.push(render::hud(&world));
+            let status = if rewinding {
+                format!("<< REWIND {:.1}s", rewind.seconds())
+            } else if let Some((event, _)) = feed.last() {
+                event.to_string()
+            } else {
+                message.clone()
+            };
+            lines.push(status);
+            terminal.draw(&lines)?;
+        }
+    }
+
+    fn save_replay(&self, replay: Option<&Replay>) {
+        if let (Some(path), Some(replay)) = (&self.record, replay) {
+            match replay.save(path) {
+                Ok(()) => println!("replay saved to {}", path.display()),
+                Err(e) => println!("Failed to save replay: {}", e),
+            }
         }
     }
 }
 
-fn load_texture(config_dir: PathBuf) -> Rc<Texture> {
-    let mut ship_asset_path = config_dir;
-    ship_asset_path.push("img");
-    ship_asset_path.push("ship2.png");
-    Rc::new(
-        Texture::from_path(
-            ship_asset_path,
-            &TextureSettings::new(),
-        ).unwrap(),
-    )
+/// Restores `snapshot` but keeps the controls the player is holding now.
+fn restore(world: &mut World, snapshot: Snapshot) {
+    let actions = world.ship().actions.clone();
+    world.restore(snapshot);
+    world.ship_mut().actions = actions;
 }
 
-fn generate_roids(scene: &mut Scene<Texture>, texture: Rc<Texture>, rng: &mut ThreadRng, n: i32, config: RoidConfig) -> Vec<Roid> {
-    let mut v = Vec::new();
-    for _ in 0..n {
-        v.push(Roid::new(config.clone(), texture.clone(), scene, rng));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_added_since_the_first_config_have_defaults() {
+        let keys: KeyConfig = ron::de::from_str("(rotate_cw: S, rotate_ccw: A, thrust: W, fire_laser: Space)").unwrap();
+        assert_eq!(keys.brake, Key::LShift);
+        assert_eq!(keys.toggle_debug, Key::F3);
+        assert_eq!(keys.rewind, Key::Backspace);
     }
-    v
-}
\ No newline at end of file
+
+    #[test]
+    fn test_terminal_brakes_without_a_modifier() {
+        let keys: KeyConfig = ron::de::from_str("(rotate_cw: S, rotate_ccw: A, thrust: W, fire_laser: Space)").unwrap();
+        assert_eq!(keys.for_terminal().brake, TERMINAL_BRAKE);
+        let keys = KeyConfig { brake: Key::Z, ..keys };
+        assert_eq!(keys.for_terminal().brake, Key::Z);
+    }
+}
@@ -0,0 +1,102 @@
+use std::fs::File;
+use std::path::PathBuf;
+use serde::Deserialize;
+use ron::de::{from_reader, from_str};
+
+pub mod ai;
+pub mod assets;
+pub mod audio;
+pub mod debug;
+pub mod ecs;
+pub mod env;
+pub mod events;
+pub mod particles;
+pub mod profiler;
+pub mod render;
+pub mod replay;
+pub mod rewind;
+pub mod scoring;
+pub mod snapshot;
+pub mod spatial;
+pub mod stats;
+pub mod tui;
+pub mod waves;
+pub mod weapons;
+pub mod world;
+pub mod systems;
+pub mod game;
+pub mod utils;
+pub mod components;
+
+use crate::ai::AutopilotConfig;
+use crate::assets::Assets;
+use crate::components::{ship::ShipConfig, laser::LaserConfig, roid::RoidConfig};
+use crate::scoring::ScoringConfig;
+use crate::stats::Achievements;
+use crate::weapons::Weapon;
+use crate::game::KeyConfig;
+use crate::waves::Waves;
+
+pub const VIEW_W: f64 = 1024.0;
+pub const VIEW_H: f64 = 768.0;
+
+#[derive(Deserialize, Clone)]
+pub struct GameConfig {
+    pub ship_config: ShipConfig,
+    pub key_config: KeyConfig,
+    pub laser_config: LaserConfig,
+    /// Weapons the ship can cycle through after the laser from `laser_config`.
+    #[serde(default)]
+    pub weapons: Vec<Weapon>,
+    pub roid_config: RoidConfig,
+    #[serde(default)]
+    pub scoring: ScoringConfig,
+    /// Seconds of play the rewind key can go back.
+    #[serde(default = "default_rewind_seconds")]
+    pub rewind_seconds: f64,
+    #[serde(default)]
+    pub autopilot_config: AutopilotConfig,
+}
+
+fn default_rewind_seconds() -> f64 {
+    5.0
+}
+
+/// Reads `config.ron` from `config_dir` when given, otherwise from the assets.
+pub fn load_config(config_dir: Option<PathBuf>, assets: &Assets) -> Result<GameConfig, String> {
+    let parsed = match config_dir {
+        Some(mut config_dir) => {
+            config_dir.push("config.ron");
+            let f = File::open(&config_dir).map_err(|e| format!("{}: {}", config_dir.display(), e))?;
+            from_reader(f)
+        }
+        None => from_str(&assets.config()),
+    };
+    parsed.map_err(|e| e.to_string())
+}
+
+/// Reads `waves.ron` from `config_dir` if it has one, otherwise from the assets.
+pub fn load_waves(config_dir: Option<PathBuf>, assets: &Assets) -> Result<Waves, String> {
+    let parsed = match config_dir.map(|dir| dir.join("waves.ron")) {
+        Some(path) if path.exists() => {
+            let f = File::open(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            from_reader(f)
+        }
+        _ => from_str(&assets.waves()),
+    };
+    let waves: Waves = parsed.map_err(|e| e.to_string())?;
+    waves.check()?;
+    Ok(waves)
+}
+
+/// Reads `achievements.ron` from `config_dir` if it has one, otherwise from the assets.
+pub fn load_achievements(config_dir: Option<PathBuf>, assets: &Assets) -> Result<Achievements, String> {
+    let parsed = match config_dir.map(|dir| dir.join("achievements.ron")) {
+        Some(path) if path.exists() => {
+            let f = File::open(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            from_reader(f)
+        }
+        _ => from_str(&assets.achievements()),
+    };
+    parsed.map_err(|e| e.to_string())
+}
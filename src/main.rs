@@ -1,41 +1,96 @@
-#[macro_use]
-extern crate structopt;
-
-use opengl_graphics::{GlGraphics, Texture};
-use sprite::*;
+use opengl_graphics::GlGraphics;
 use piston_window::{OpenGL, Size, WindowSettings};
-use std::{fs::File};
-use serde::Deserialize;
-use ron::de::from_reader;
-
-mod game;
-mod utils;
-mod components;
 
-use game::Game;
-use crate::components::{ship::ShipConfig, laser::LaserConfig, roid::RoidConfig};
-use crate::game::{KeyConfig, GeneratorConfig};
+use stroids_clone::ai::Autopilot;
+use stroids_clone::assets::Assets;
+use stroids_clone::audio;
+use stroids_clone::game::Game;
+use stroids_clone::profiler::Profiler;
+use stroids_clone::snapshot::Snapshot;
+use stroids_clone::stats::Records;
+use stroids_clone::{load_achievements, load_config, load_waves, VIEW_H, VIEW_W};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
-const VIEW_W: f64 = 1024.0;
-const VIEW_H: f64 = 768.0;
-
 #[derive(Debug, StructOpt)]
 #[structopt(name = "stroids", about = "stroids.")]
 struct Opt {
-    /// Input file
+    /// Dir containing config.ron, waves.ron and achievements.ron, overrides the ones in the assets dir
     #[structopt(short, long, parse(from_os_str), env = "STROIDS_CONFIG_PATH")]
     pub config: Option<PathBuf>,
+    /// Assets dir, any file found here overrides the built-in copy. Sounds are only read from its sounds/ dir
     #[structopt(short, long, parse(from_os_str), env = "STROIDS_ASSETS_PATH")]
     pub assets: Option<PathBuf>,
+    /// Write per-frame phase timings to this CSV file
+    #[structopt(long, parse(from_os_str))]
+    pub profile_out: Option<PathBuf>,
+    /// Let the AI fly the ship, for demos and soak tests
+    #[structopt(long)]
+    pub autopilot: bool,
+    /// Lifetime stats and unlocked achievements are kept in this file
+    #[structopt(long, parse(from_os_str), default_value = "stroids-stats.ron", env = "STROIDS_STATS_PATH")]
+    pub stats: PathBuf,
+    /// File the quicksave and quickload keys write to and read from
+    #[structopt(long, parse(from_os_str), default_value = "stroids-quicksave.ron")]
+    pub quicksave: PathBuf,
+    /// Carry on from a snapshot written by quicksave instead of starting a new game
+    #[structopt(long, parse(from_os_str))]
+    pub load_snapshot: Option<PathBuf>,
+    /// Record the session and save it as a replay here on exit, for stroids-render
+    #[structopt(long, parse(from_os_str))]
+    pub record: Option<PathBuf>,
+    /// Play in the terminal instead of a window, for machines with no display. A brake bound to a modifier moves to B and there's no debug overlay
+    #[structopt(long)]
+    pub terminal: bool,
 }
 
 fn main() {
     let opt = Opt::from_args();
-    let config_dir = Option::<PathBuf>::expect(opt.config, "No path to config dir");
-    let assets_dir = Option::<PathBuf>::expect(opt.assets, "No path to assets dir");
-    let game_config = load_cfg(config_dir.clone());
+    let assets = Assets::new(opt.assets);
+    let game_config = load_config(opt.config.clone(), &assets).unwrap_or_else(|e| {
+        println!("Failed to load config: {}", e);
+        std::process::exit(1);
+    });
+    let waves = load_waves(opt.config.clone(), &assets).unwrap_or_else(|e| {
+        println!("Failed to load waves: {}", e);
+        std::process::exit(1);
+    });
+    let achievements = load_achievements(opt.config, &assets).unwrap_or_else(|e| {
+        println!("Failed to load achievements: {}", e);
+        std::process::exit(1);
+    });
+    let records = Records::load(opt.stats, achievements).unwrap_or_else(|e| {
+        println!("Failed to load stats: {}", e);
+        std::process::exit(1);
+    });
+    let profiler = match &opt.profile_out {
+        Some(path) => Profiler::with_csv(path).unwrap_or_else(|e| panic!("Failed to create {}: {}", path.display(), e)),
+        None => Profiler::new(),
+    };
+    let autopilot = if opt.autopilot {
+        Some(Autopilot::new(game_config.autopilot_config.clone()))
+    } else {
+        None
+    };
+    let mut game = Game::new(profiler, autopilot, records, opt.quicksave);
+    if let Some(path) = &opt.load_snapshot {
+        let snapshot = Snapshot::load(path).unwrap_or_else(|e| {
+            println!("Failed to load snapshot: {}", e);
+            std::process::exit(1);
+        });
+        game.resume(snapshot);
+    }
+    if let Some(path) = opt.record {
+        game.record(path);
+    }
+    if opt.terminal {
+        if let Err(e) = game.run_terminal(game_config, waves) {
+            println!("Terminal error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let game_title = "Stroids...";
     let game_window_size = Size {
         width: VIEW_W,
@@ -52,30 +107,14 @@ fn main() {
     .build()
     .unwrap_or_else(|error| panic!("Failed to build PistonWindow: {}", error));
 
-    let mut scene = Scene::<Texture>::new();
-
-    let mut game = Game::new();
     let mut gl = GlGraphics::new(opengl);
-    game.run(&mut window, &mut gl, &mut scene, game_config, assets_dir.clone());
-}
-
-#[derive(Deserialize)]
-pub struct GameConfig {
-    ship_config: ShipConfig,
-    key_config: KeyConfig,
-    generator_config: GeneratorConfig,
-    laser_config: LaserConfig,
-    roid_config: RoidConfig,
-}
-
-fn load_cfg(mut config_dir: PathBuf) -> GameConfig {
-    config_dir.push("config.ron");
-    let f = File::open(config_dir).expect("Failed to open file");
-    match from_reader(f) {
-        Ok(x) => x,
-        Err(e) => {
-            println!("Failed to load config: {}", e);
-            std::process::exit(1);
-        }
+    let mut result = Ok(());
+    audio::with_sounds(&assets, |sounds| {
+        game.play_sounds(sounds);
+        result = game.run(&mut window, &mut gl, game_config, waves, &assets);
+    });
+    if let Err(e) = result {
+        println!("Failed to load assets: {}", e);
+        std::process::exit(1);
     }
 }
@@ -0,0 +1,105 @@
+use rand::Rng;
+
+use crate::events::{GameEvent, Subscriber};
+use crate::render::{Color, Renderer};
+use crate::utils::{angle_to_vector, Vector};
+
+/// Seconds a particle lasts.
+const LIFETIME: f64 = 0.8;
+const ROID_DUST: Color = [0.8, 0.7, 0.6, 1.0];
+const SHIP_DEBRIS: Color = [1.0, 0.6, 0.2, 1.0];
+
+struct Particle {
+    pos: Vector,
+    /// px per second.
+    vel: Vector,
+    remaining: f64,
+    color: Color,
+}
+
+/// Bursts of dust where roids break up and debris where the ship dies.
+/// Only for show, so they use their own randomness and stay out of the
+/// world, its snapshots and replays.
+#[derive(Default)]
+pub struct Particles {
+    particles: Vec<Particle>,
+}
+
+impl Particles {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// `count` particles flying out of `pos` at up to `speed` px per second.
+    fn burst(&mut self, pos: Vector, count: usize, speed: f64, color: Color) {
+        let mut rng = rand::thread_rng();
+        for _ in 0..count {
+            self.particles.push(Particle {
+                pos,
+                vel: angle_to_vector(rng.gen_range(speed * 0.2, speed), rng.gen_range(0.0, 360.0)),
+                remaining: rng.gen_range(LIFETIME * 0.5, LIFETIME),
+                color,
+            });
+        }
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        for particle in &mut self.particles {
+            particle.pos += particle.vel * dt;
+            particle.remaining -= dt;
+        }
+        self.particles.retain(|particle| particle.remaining > 0.0);
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Vector> + '_ {
+        self.particles.iter().map(|particle| particle.pos)
+    }
+
+    /// Draws the particles fading out as they age.
+    pub fn draw<R: Renderer>(&self, renderer: &mut R) {
+        for particle in &self.particles {
+            let mut color = particle.color;
+            color[3] = (particle.remaining / LIFETIME) as f32;
+            renderer.disc(color, particle.pos, 1.5);
+        }
+    }
+}
+
+impl Subscriber for Particles {
+    fn notify(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::RoidDestroyed { pos, diameter, .. } => {
+                self.burst(pos, (diameter / 4.0) as usize + 4, diameter * 2.0, ROID_DUST)
+            }
+            GameEvent::ShipKilled { pos, .. } => self.burst(pos, 40, 150.0, SHIP_DEBRIS),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::roid::RoidSize;
+
+    #[test]
+    fn test_bursts_fade_out() {
+        let mut particles = Particles::new();
+        let pos = Vector::new(100.0, 100.0);
+        particles.notify(&GameEvent::RoidDestroyed { size: RoidSize::Large, pos, diameter: 48.0 });
+        particles.notify(&GameEvent::ShotMissed);
+        assert_eq!(particles.len(), 16);
+        particles.update(0.1);
+        assert!(particles.positions().all(|moved| moved != pos));
+        particles.update(LIFETIME);
+        assert!(particles.is_empty());
+    }
+}
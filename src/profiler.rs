@@ -0,0 +1,259 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const WINDOW_SIZE: usize = 120;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Phase {
+    Input,
+    Movement,
+    Collision,
+    Spawning,
+    Render,
+}
+
+const PHASES: [Phase; 5] = [Phase::Input, Phase::Movement, Phase::Collision, Phase::Spawning, Phase::Render];
+
+impl Phase {
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Input => "input",
+            Phase::Movement => "movement",
+            Phase::Collision => "collision",
+            Phase::Spawning => "spawning",
+            Phase::Render => "render",
+        }
+    }
+}
+
+/// The last `capacity` samples of something, oldest first.
+pub struct Window {
+    samples: VecDeque<Duration>,
+    capacity: usize,
+}
+
+impl Window {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, sample: Duration) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn mean(&self) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::default();
+        }
+        self.samples.iter().sum::<Duration>() / self.samples.len() as u32
+    }
+
+    pub fn min(&self) -> Duration {
+        self.samples.iter().min().cloned().unwrap_or_default()
+    }
+
+    pub fn max(&self) -> Duration {
+        self.samples.iter().max().cloned().unwrap_or_default()
+    }
+
+    /// Nearest-rank percentile, `p` in 0..=100.
+    pub fn percentile(&self, p: f64) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::default();
+        }
+        let mut sorted: Vec<Duration> = self.samples.iter().cloned().collect();
+        sorted.sort();
+        let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+        sorted[rank.max(1).min(sorted.len()) - 1]
+    }
+}
+
+/// Frame and per-phase timings over a rolling window, optionally dumped per frame to CSV.
+pub struct Profiler {
+    started: Instant,
+    last_frame: Instant,
+    frames: Window,
+    phases: Vec<Window>,
+    current: [Duration; 5],
+    update_ticks: VecDeque<Instant>,
+    frame_count: u64,
+    csv: Option<BufWriter<File>>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            last_frame: now,
+            frames: Window::new(WINDOW_SIZE),
+            phases: PHASES.iter().map(|_| Window::new(WINDOW_SIZE)).collect(),
+            current: [Duration::default(); 5],
+            update_ticks: VecDeque::new(),
+            frame_count: 0,
+            csv: None,
+        }
+    }
+
+    pub fn with_csv(path: &Path) -> io::Result<Self> {
+        let mut csv = BufWriter::new(File::create(path)?);
+        write!(csv, "frame,elapsed_s,frame_ms")?;
+        for phase in PHASES.iter() {
+            write!(csv, ",{}_ms", phase.name())?;
+        }
+        writeln!(csv)?;
+        let mut profiler = Self::new();
+        profiler.csv = Some(csv);
+        Ok(profiler)
+    }
+
+    /// Adds the time since `start` to `phase` for the current frame.
+    pub fn record(&mut self, phase: Phase, start: Instant) {
+        self.current[phase as usize] += start.elapsed();
+    }
+
+    pub fn tick_update(&mut self) {
+        let now = Instant::now();
+        self.update_ticks.push_back(now);
+        while let Some(&oldest) = self.update_ticks.front() {
+            if now.duration_since(oldest) <= Duration::from_secs(1) {
+                break;
+            }
+            self.update_ticks.pop_front();
+        }
+    }
+
+    /// Closes the current frame, call once per render after the `Render` phase is recorded.
+    pub fn end_frame(&mut self) {
+        let now = Instant::now();
+        let frame_time = now.duration_since(self.last_frame);
+        self.last_frame = now;
+        self.frame_count += 1;
+        self.frames.push(frame_time);
+        for (window, time) in self.phases.iter_mut().zip(self.current.iter()) {
+            window.push(*time);
+        }
+        if let Some(csv) = &mut self.csv {
+            let mut row = format!(
+                "{},{:.3},{:.3}",
+                self.frame_count,
+                now.duration_since(self.started).as_secs_f64(),
+                ms(frame_time),
+            );
+            for time in self.current.iter() {
+                row.push_str(&format!(",{:.3}", ms(*time)));
+            }
+            if let Err(e) = writeln!(csv, "{}", row) {
+                println!("Failed to write profile, disabling: {}", e);
+                self.csv = None;
+            }
+        }
+        self.current = [Duration::default(); 5];
+    }
+
+    pub fn phase(&self, phase: Phase) -> &Window {
+        &self.phases[phase as usize]
+    }
+
+    pub fn fps(&self) -> f64 {
+        let mean = self.frames.mean().as_secs_f64();
+        if mean == 0.0 { 0.0 } else { 1.0 / mean }
+    }
+
+    pub fn tps(&self) -> usize {
+        self.update_ticks.len()
+    }
+
+    pub fn formatted(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!(
+                "FPS: {:.1} TPS: {} ELP: {}",
+                self.fps(),
+                self.tps(),
+                self.started.elapsed().as_secs(),
+            ),
+            format!(
+                "frame ms min: {:.2} max: {:.2} p50: {:.2} p95: {:.2} p99: {:.2}",
+                ms(self.frames.min()),
+                ms(self.frames.max()),
+                ms(self.frames.percentile(50.0)),
+                ms(self.frames.percentile(95.0)),
+                ms(self.frames.percentile(99.0)),
+            ),
+        ];
+        for phase in PHASES.iter() {
+            let window = self.phase(*phase);
+            lines.push(format!(
+                "{} ms avg: {:.3} max: {:.3}",
+                phase.name(),
+                ms(window.mean()),
+                ms(window.max()),
+            ));
+        }
+        lines
+    }
+
+    pub fn flush(&mut self) {
+        if let Some(csv) = &mut self.csv {
+            if let Err(e) = csv.flush() {
+                println!("Failed to flush profile: {}", e);
+            }
+        }
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window_of(ms: &[u64]) -> Window {
+        let mut window = Window::new(ms.len());
+        for m in ms {
+            window.push(Duration::from_millis(*m));
+        }
+        window
+    }
+
+    #[test]
+    fn test_window_drops_oldest() {
+        let mut window = window_of(&[5, 1, 2]);
+        window.push(Duration::from_millis(3));
+        assert_eq!(window.max(), Duration::from_millis(3));
+        assert_eq!(window.min(), Duration::from_millis(1));
+        assert_eq!(window.mean(), Duration::from_millis(2));
+    }
+
+    #[test]
+    fn test_window_percentile() {
+        let window = window_of(&[10, 1, 9, 2, 8, 3, 7, 4, 6, 5]);
+        assert_eq!(window.percentile(50.0), Duration::from_millis(5));
+        assert_eq!(window.percentile(95.0), Duration::from_millis(10));
+        assert_eq!(window.percentile(0.0), Duration::from_millis(1));
+    }
+
+    #[test]
+    fn test_empty_window() {
+        let window = Window::new(4);
+        assert_eq!(window.mean(), Duration::default());
+        assert_eq!(window.percentile(99.0), Duration::default());
+    }
+}
@@ -0,0 +1,63 @@
+use opengl_graphics::{GlGraphics, GlyphCache, Texture};
+use piston_window::{clear, ellipse, line, Context, Ellipse, Image, Transformed};
+
+use crate::render::{Color, Renderer};
+use crate::utils::Vector;
+
+/// Draws to the window through OpenGL.
+pub struct GlRenderer<'a, 'b> {
+    context: Context,
+    graphics: &'a mut GlGraphics,
+    texture: &'a Texture,
+    glyphs: &'a mut GlyphCache<'b>,
+}
+
+impl<'a, 'b> GlRenderer<'a, 'b> {
+    pub fn new(context: Context, graphics: &'a mut GlGraphics, texture: &'a Texture, glyphs: &'a mut GlyphCache<'b>) -> Self {
+        Self {
+            context,
+            graphics,
+            texture,
+            glyphs,
+        }
+    }
+}
+
+impl<'a, 'b> Renderer for GlRenderer<'a, 'b> {
+    fn clear(&mut self, color: Color) {
+        clear(color, self.graphics);
+    }
+
+    fn sprite(&mut self, rect: [f64; 4], pos: Vector, rot: f64, scale: f64, tint: Option<[f32; 3]>) {
+        let (w, h) = (rect[2], rect[3]);
+        let transform = self.context.transform
+            .trans(pos.x, pos.y)
+            .rot_deg(rot)
+            .scale(scale, scale)
+            .trans(-w / 2.0, -h / 2.0);
+        let color = tint.map_or([1.0; 4], |[r, g, b]| [r, g, b, 1.0]);
+        Image::new_color(color)
+            .src_rect(rect)
+            .rect([0.0, 0.0, w, h])
+            .draw(self.texture, &self.context.draw_state, transform, self.graphics);
+    }
+
+    fn disc(&mut self, color: Color, center: Vector, radius: f64) {
+        let circle = ellipse::circle(center.x, center.y, radius);
+        Ellipse::new(color).draw(circle, &self.context.draw_state, self.context.transform, self.graphics);
+    }
+
+    fn ring(&mut self, color: Color, center: Vector, radius: f64, thickness: f64) {
+        let circle = ellipse::circle(center.x, center.y, radius);
+        Ellipse::new_border(color, thickness / 2.0).draw(circle, &self.context.draw_state, self.context.transform, self.graphics);
+    }
+
+    fn line(&mut self, color: Color, from: Vector, to: Vector, thickness: f64) {
+        line(color, thickness / 2.0, [from.x, from.y, to.x, to.y], self.context.transform, self.graphics);
+    }
+
+    fn text(&mut self, color: Color, size: u32, text: &str, pos: Vector) {
+        let transform = self.context.transform.trans(pos.x, pos.y);
+        let _ = piston_window::text(color, size, text, self.glyphs, transform, self.graphics);
+    }
+}
@@ -0,0 +1,48 @@
+use crate::debug::DebugOverlay;
+use crate::systems;
+use crate::utils::{Vector, BLACK};
+use crate::world::World;
+
+pub mod font;
+pub mod gl;
+pub mod soft;
+pub mod term;
+
+pub type Color = [f32; 4];
+
+/// Something frames can be drawn to, a window or an image in memory.
+/// Positions are in playfield px and rotations in degrees clockwise.
+pub trait Renderer {
+    fn clear(&mut self, color: Color);
+    /// Draws `rect` of the sprite sheet centred on `pos`, turned by `rot`,
+    /// scaled by `scale` and multiplied by `tint`.
+    fn sprite(&mut self, rect: [f64; 4], pos: Vector, rot: f64, scale: f64, tint: Option<[f32; 3]>);
+    /// A filled circle.
+    fn disc(&mut self, color: Color, center: Vector, radius: f64);
+    /// A circle outline `thickness` px wide.
+    fn ring(&mut self, color: Color, center: Vector, radius: f64, thickness: f64);
+    fn line(&mut self, color: Color, from: Vector, to: Vector, thickness: f64);
+    /// `text` starting at `pos` on its baseline, `size` px tall.
+    fn text(&mut self, color: Color, size: u32, text: &str, pos: Vector);
+}
+
+/// The playfield, the HUD and, when it's enabled, the debug overlay.
+pub fn draw_frame<R: Renderer>(renderer: &mut R, world: &World, debug: &DebugOverlay, stats: &[String]) {
+    renderer.clear(BLACK);
+    systems::render(&world.ecs, renderer);
+    renderer.text([1.0; 4], 14, &hud(world), Vector::new(50.0, 50.0));
+    debug.draw(stats, world, renderer);
+}
+
+pub fn hud(world: &World) -> String {
+    let arsenal = world.arsenal();
+    let weapon = match arsenal.ammo() {
+        Some(ammo) => format!("{} ({})", arsenal.current().name, ammo),
+        None => arsenal.current().name.clone(),
+    };
+    let scoring = &world.scoring;
+    format!(
+        "Targets Remaining: {} Score: {} x{} Lives: {} Weapon: {}",
+        world.ecs.roids.len(), scoring.score, scoring.multiplier(), scoring.lives, weapon,
+    )
+}
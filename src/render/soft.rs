@@ -0,0 +1,222 @@
+use std::path::Path;
+
+use image::{Rgba, RgbaImage};
+
+use crate::render::font::{glyph, GLYPH_H, GLYPH_W};
+use crate::render::{Color, Renderer};
+use crate::utils::Vector;
+
+/// Font px per point of text size, so text comes out about as tall as the
+/// text the window draws.
+const FONT_SCALE: f64 = 0.1;
+
+/// Rasterizes frames into an RGBA image on the CPU, for machines without a
+/// GPU. Pixels are sampled at their centres with no antialiasing, so the
+/// same frame always comes out the same.
+pub struct SoftRenderer {
+    frame: RgbaImage,
+    atlas: RgbaImage,
+}
+
+impl SoftRenderer {
+    /// `atlas` is the sprite sheet sprites are cut from.
+    pub fn new(width: u32, height: u32, atlas: RgbaImage) -> Self {
+        Self {
+            frame: RgbaImage::new(width, height),
+            atlas,
+        }
+    }
+
+    pub fn frame(&self) -> &RgbaImage {
+        &self.frame
+    }
+
+    pub fn save_png(&self, path: &Path) -> Result<(), String> {
+        self.frame.save(path).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+/// Blends the colour `inside` gives over every pixel in the box from `min`
+/// to `max` whose centre it says is covered.
+fn fill<F: Fn(Vector) -> Option<Color>>(frame: &mut RgbaImage, min: Vector, max: Vector, inside: F) {
+    let (width, height) = frame.dimensions();
+    let x0 = min.x.floor().max(0.0) as u32;
+    let y0 = min.y.floor().max(0.0) as u32;
+    let x1 = (max.x.ceil().max(0.0) as u32).min(width);
+    let y1 = (max.y.ceil().max(0.0) as u32).min(height);
+    for y in y0..y1 {
+        for x in x0..x1 {
+            if let Some(color) = inside(Vector::new(f64::from(x) + 0.5, f64::from(y) + 0.5)) {
+                blend(frame.get_pixel_mut(x, y), color);
+            }
+        }
+    }
+}
+
+/// Source-over blending of `color` onto `pixel`.
+fn blend(pixel: &mut Rgba<u8>, color: Color) {
+    let alpha = color[3].clamp(0.0, 1.0);
+    for channel in 0..3 {
+        let under = f32::from(pixel[channel]) / 255.0;
+        let over = color[channel].clamp(0.0, 1.0);
+        pixel[channel] = ((over * alpha + under * (1.0 - alpha)) * 255.0).round() as u8;
+    }
+    let under = f32::from(pixel[3]) / 255.0;
+    pixel[3] = ((alpha + under * (1.0 - alpha)) * 255.0).round() as u8;
+}
+
+/// Distance from `p` to the segment from `a` to `b`.
+fn segment_distance(p: Vector, a: Vector, b: Vector) -> f64 {
+    let ab = b - a;
+    let t = if ab.length_squared() == 0.0 {
+        0.0
+    } else {
+        ((p - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+    };
+    p.distance(a + ab * t)
+}
+
+impl Renderer for SoftRenderer {
+    fn clear(&mut self, color: Color) {
+        let pixel = Rgba([
+            (color[0] * 255.0).round() as u8,
+            (color[1] * 255.0).round() as u8,
+            (color[2] * 255.0).round() as u8,
+            (color[3] * 255.0).round() as u8,
+        ]);
+        for p in self.frame.pixels_mut() {
+            *p = pixel;
+        }
+    }
+
+    fn sprite(&mut self, rect: [f64; 4], pos: Vector, rot: f64, scale: f64, tint: Option<[f32; 3]>) {
+        if scale <= 0.0 {
+            return;
+        }
+        let size = Vector::new(rect[2], rect[3]);
+        let reach = size.length() / 2.0 * scale;
+        let atlas = &self.atlas;
+        let [r, g, b] = tint.unwrap_or([1.0; 3]);
+        let inside = |p: Vector| {
+            // back into the sprite's own px, with its top left at 0, 0
+            let local = (p - pos).rotate(-rot) / scale + size / 2.0;
+            if local.x < 0.0 || local.y < 0.0 || local.x >= size.x || local.y >= size.y {
+                return None;
+            }
+            let x = (rect[0] + local.x).floor() as u32;
+            let y = (rect[1] + local.y).floor() as u32;
+            if x >= atlas.width() || y >= atlas.height() {
+                return None;
+            }
+            let texel = atlas.get_pixel(x, y);
+            let channel = |i: usize| f32::from(texel[i]) / 255.0;
+            Some([channel(0) * r, channel(1) * g, channel(2) * b, channel(3)])
+        };
+        fill(&mut self.frame, pos - Vector::new(reach, reach), pos + Vector::new(reach, reach), inside);
+    }
+
+    fn disc(&mut self, color: Color, center: Vector, radius: f64) {
+        let extent = Vector::new(radius, radius);
+        fill(&mut self.frame, center - extent, center + extent, |p| {
+            if p.distance(center) <= radius {
+                Some(color)
+            } else {
+                None
+            }
+        });
+    }
+
+    fn ring(&mut self, color: Color, center: Vector, radius: f64, thickness: f64) {
+        let half = thickness / 2.0;
+        let extent = Vector::new(radius + half, radius + half);
+        fill(&mut self.frame, center - extent, center + extent, |p| {
+            if (p.distance(center) - radius).abs() <= half {
+                Some(color)
+            } else {
+                None
+            }
+        });
+    }
+
+    fn line(&mut self, color: Color, from: Vector, to: Vector, thickness: f64) {
+        let half = thickness / 2.0;
+        let extent = Vector::new(half, half);
+        fill(&mut self.frame, from.min(to) - extent, from.max(to) + extent, |p| {
+            if segment_distance(p, from, to) <= half {
+                Some(color)
+            } else {
+                None
+            }
+        });
+    }
+
+    fn text(&mut self, color: Color, size: u32, text: &str, pos: Vector) {
+        let px = f64::from(size) * FONT_SCALE;
+        let glyph_size = Vector::new(f64::from(GLYPH_W), f64::from(GLYPH_H)) * px;
+        let mut origin = pos - Vector::new(0.0, glyph_size.y);
+        for c in text.chars() {
+            let rows = glyph(c);
+            let top_left = origin;
+            fill(&mut self.frame, top_left, top_left + glyph_size, |p| {
+                let cell = (p - top_left) / px;
+                let (col, row) = (cell.x.floor() as u32, cell.y.floor() as u32);
+                if col >= GLYPH_W || row >= GLYPH_H {
+                    return None;
+                }
+                if rows[row as usize] & (1 << (GLYPH_W - 1 - col)) != 0 {
+                    Some(color)
+                } else {
+                    None
+                }
+            });
+            origin.x += f64::from(GLYPH_W + 1) * px;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn atlas() -> RgbaImage {
+        // left half red, right half blue
+        RgbaImage::from_fn(8, 4, |x, _| if x < 4 { Rgba([255, 0, 0, 255]) } else { Rgba([0, 0, 255, 255]) })
+    }
+
+    #[test]
+    fn test_sprite_is_rotated_and_tinted() {
+        let mut renderer = SoftRenderer::new(16, 16, atlas());
+        renderer.clear([0.0, 0.0, 0.0, 1.0]);
+        // turned a quarter clockwise, the left half ends up on top
+        renderer.sprite([0.0, 0.0, 8.0, 4.0], Vector::new(8.0, 8.0), 90.0, 1.0, None);
+        assert_eq!(renderer.frame().get_pixel(8, 5), &Rgba([255, 0, 0, 255]));
+        assert_eq!(renderer.frame().get_pixel(8, 10), &Rgba([0, 0, 255, 255]));
+        assert_eq!(renderer.frame().get_pixel(3, 8), &Rgba([0, 0, 0, 255]));
+        renderer.sprite([0.0, 0.0, 4.0, 4.0], Vector::new(8.0, 8.0), 0.0, 1.0, Some([0.5, 1.0, 1.0]));
+        assert_eq!(renderer.frame().get_pixel(8, 8), &Rgba([128, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_shapes_blend_and_clip() {
+        let mut renderer = SoftRenderer::new(10, 10, atlas());
+        renderer.clear([0.0, 0.0, 0.0, 1.0]);
+        renderer.disc([1.0, 1.0, 1.0, 0.5], Vector::new(0.0, 0.0), 3.0);
+        assert_eq!(renderer.frame().get_pixel(0, 0), &Rgba([128, 128, 128, 255]));
+        assert_eq!(renderer.frame().get_pixel(5, 5), &Rgba([0, 0, 0, 255]));
+        renderer.line([0.0, 1.0, 0.0, 1.0], Vector::new(0.0, 9.5), Vector::new(20.0, 9.5), 1.0);
+        assert_eq!(renderer.frame().get_pixel(9, 9), &Rgba([0, 255, 0, 255]));
+        assert_eq!(renderer.frame().get_pixel(9, 8), &Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_text_sits_on_the_baseline() {
+        let mut renderer = SoftRenderer::new(20, 12, atlas());
+        renderer.clear([0.0, 0.0, 0.0, 1.0]);
+        renderer.text([1.0; 4], 10, "|", Vector::new(0.0, 10.0));
+        let lit: Vec<(u32, u32)> = renderer.frame().enumerate_pixels()
+            .filter(|(_, _, p)| p[0] == 255)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        assert_eq!(lit, (3..10).map(|y| (2, y)).collect::<Vec<_>>());
+    }
+}
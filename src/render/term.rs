@@ -0,0 +1,183 @@
+use crate::components::roid::RoidSize;
+use crate::components::well::WellKind;
+use crate::ecs::{Ecs, Entity};
+use crate::utils::Vector;
+use crate::world::World;
+use crate::{VIEW_H, VIEW_W};
+
+/// The ship pointing up, then every 45 degrees clockwise.
+const ARROWS: [char; 8] = ['↑', '↗', '→', '↘', '↓', '↙', '←', '↖'];
+const SHOT: char = '·';
+const MISSILE: char = '+';
+const GRAVITY_WELL: char = '◎';
+const BLACK_HOLE: char = '●';
+
+fn roid_char(size: RoidSize) -> char {
+    match size {
+        RoidSize::Large => '@',
+        RoidSize::Medium => 'O',
+        RoidSize::Small => 'o',
+    }
+}
+
+/// The arrow closest to a ship turned `rot` degrees clockwise.
+pub fn arrow(rot: f64) -> char {
+    let step = (rot / 45.0).round() as i64;
+    ARROWS[step.rem_euclid(8) as usize]
+}
+
+/// The playfield squeezed into a grid of characters, one per terminal cell.
+pub struct TextFrame {
+    cols: usize,
+    rows: usize,
+    cells: Vec<char>,
+}
+
+impl TextFrame {
+    pub fn new(cols: usize, rows: usize) -> Self {
+        Self {
+            cols: cols.max(1),
+            rows: rows.max(1),
+            cells: vec![' '; cols.max(1) * rows.max(1)],
+        }
+    }
+
+    /// Size of a cell in playfield px.
+    fn cell(&self) -> Vector {
+        Vector::new(VIEW_W / self.cols as f64, VIEW_H / self.rows as f64)
+    }
+
+    fn set(&mut self, col: i64, row: i64, c: char) {
+        let col = col.rem_euclid(self.cols as i64) as usize;
+        let row = row.rem_euclid(self.rows as i64) as usize;
+        self.cells[row * self.cols + col] = c;
+    }
+
+    /// Puts `c` in the cell `pos` falls in.
+    pub fn put(&mut self, pos: Vector, c: char) {
+        let cell = self.cell();
+        self.set((pos.x / cell.x).floor() as i64, (pos.y / cell.y).floor() as i64, c);
+    }
+
+    /// Fills every cell whose centre is within `radius` of `center`, wrapping
+    /// around the edges like the playfield does, and always the cell
+    /// `center` is in so small things don't vanish.
+    pub fn disc(&mut self, center: Vector, radius: f64, c: char) {
+        let cell = self.cell();
+        let (min_col, max_col) = (((center.x - radius) / cell.x).floor() as i64, ((center.x + radius) / cell.x).ceil() as i64);
+        let (min_row, max_row) = (((center.y - radius) / cell.y).floor() as i64, ((center.y + radius) / cell.y).ceil() as i64);
+        for row in min_row..max_row {
+            for col in min_col..max_col {
+                let middle = Vector::new((col as f64 + 0.5) * cell.x, (row as f64 + 0.5) * cell.y);
+                if middle.distance(center) <= radius {
+                    self.set(col, row, c);
+                }
+            }
+        }
+        self.put(center, c);
+    }
+
+    /// Writes `text` centred on `row`, cut off at the edges.
+    pub fn text(&mut self, row: usize, text: &str) {
+        if row >= self.rows {
+            return;
+        }
+        let len = text.chars().count();
+        let start = self.cols.saturating_sub(len) / 2;
+        for (col, c) in text.chars().take(self.cols).enumerate() {
+            self.cells[row * self.cols + start + col] = c;
+        }
+    }
+
+    /// The grid inside a box drawing border.
+    pub fn lines(&self) -> Vec<String> {
+        let edge: String = "─".repeat(self.cols);
+        let mut lines = vec![format!("┌{}┐", edge)];
+        for row in self.cells.chunks(self.cols) {
+            lines.push(format!("│{}│", row.iter().collect::<String>()));
+        }
+        lines.push(format!("└{}┘", edge));
+        lines
+    }
+}
+
+/// Draws the world into a frame `cols` by `rows` cells across, border not included.
+pub fn draw_world(world: &World, cols: usize, rows: usize) -> TextFrame {
+    let mut frame = TextFrame::new(cols, rows);
+    draw_ecs(&world.ecs, world.ship, &mut frame);
+    frame
+}
+
+/// Wells under roids under shots, with the ship on top.
+fn draw_ecs(ecs: &Ecs, ship: Entity, frame: &mut TextFrame) {
+    for (entity, well) in ecs.wells.iter() {
+        if let Some(transform) = ecs.transforms.get(entity) {
+            match well.kind {
+                WellKind::Gravity => frame.put(transform.pos, GRAVITY_WELL),
+                WellKind::BlackHole => frame.disc(transform.pos, well.horizon, BLACK_HOLE),
+            }
+        }
+    }
+    for (entity, roid) in ecs.roids.iter() {
+        if let (Some(transform), Some(collider)) = (ecs.transforms.get(entity), ecs.colliders.get(entity)) {
+            frame.disc(transform.pos, collider.radius, roid_char(roid.size));
+        }
+    }
+    for (entity, _) in ecs.projectiles.iter() {
+        if let Some(transform) = ecs.transforms.get(entity) {
+            let c = if ecs.missiles.contains(entity) { MISSILE } else { SHOT };
+            frame.put(transform.pos, c);
+        }
+    }
+    if let Some(transform) = ecs.transforms.get(ship) {
+        frame.put(transform.pos, arrow(transform.rot));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{Collider, Transform};
+    use crate::components::roid::Roid;
+
+    fn spawn(ecs: &mut Ecs, x: f64, y: f64, rot: f64) -> Entity {
+        let entity = ecs.spawn();
+        ecs.transforms.insert(entity, Transform { pos: Vector::new(x, y), rot });
+        entity
+    }
+
+    fn roid(ecs: &mut Ecs, x: f64, y: f64, size: RoidSize, radius: f64) {
+        let entity = spawn(ecs, x, y, 0.0);
+        ecs.colliders.insert(entity, Collider { radius });
+        ecs.roids.insert(entity, Roid { size, health: 1 });
+    }
+
+    #[test]
+    fn test_arrow_follows_rotation() {
+        assert_eq!(arrow(0.0), '↑');
+        assert_eq!(arrow(100.0), '→');
+        assert_eq!(arrow(-45.0), '↖');
+        assert_eq!(arrow(540.0), '↓');
+    }
+
+    #[test]
+    fn test_roids_wrap_around_the_edges() {
+        // 8 by 6 cells of 128 px
+        let mut ecs = Ecs::new();
+        let ship = spawn(&mut ecs, 512.0, 384.0, 90.0);
+        roid(&mut ecs, 0.0, 0.0, RoidSize::Large, 100.0);
+        roid(&mut ecs, 960.0, 448.0, RoidSize::Small, 5.0);
+        let mut frame = TextFrame::new(8, 6);
+        draw_ecs(&ecs, ship, &mut frame);
+        assert_eq!(frame.lines(), vec![
+            "┌────────┐",
+            "│@      @│",
+            "│        │",
+            "│        │",
+            "│    →  o│",
+            "│        │",
+            "│@      @│",
+            "└────────┘",
+        ]);
+    }
+}
@@ -0,0 +1,135 @@
+use std::fs;
+use std::path::Path;
+
+use piston_window::UpdateArgs;
+use ron::ser::{to_string_pretty, PrettyConfig};
+use serde::{Deserialize, Serialize};
+
+use crate::components::ship::Actions;
+use crate::snapshot::Snapshot;
+use crate::world::{Tick, World};
+
+/// The controls held for one tick and how long the tick was.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReplayTick {
+    pub dt: f64,
+    pub controls: Actions,
+    /// Times the weapon was cycled since the tick before.
+    #[serde(default)]
+    pub weapon_cycles: u32,
+}
+
+impl ReplayTick {
+    /// Runs the tick on `world` with the recorded input.
+    pub fn play(&self, world: &mut World) -> Tick {
+        for _ in 0..self.weapon_cycles {
+            world.cycle_weapon();
+        }
+        world.ship_mut().actions.take_controls(self.controls.clone());
+        world.update(UpdateArgs { dt: self.dt })
+    }
+}
+
+/// A recorded session, the world it started from and the input for every
+/// tick after. Played with the config and waves it was recorded with, it
+/// comes out exactly the same.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub start: Snapshot,
+    pub ticks: Vec<ReplayTick>,
+    #[serde(skip)]
+    weapon_cycles: u32,
+}
+
+impl Replay {
+    pub fn new(start: Snapshot) -> Self {
+        Self {
+            start,
+            ticks: Vec::new(),
+            weapon_cycles: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ticks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+
+    /// Seconds the recording lasts.
+    pub fn duration(&self) -> f64 {
+        self.ticks.iter().map(|tick| tick.dt).sum()
+    }
+
+    /// The weapon was cycled, it happens before the next tick recorded.
+    pub fn cycle_weapon(&mut self) {
+        self.weapon_cycles += 1;
+    }
+
+    /// Adds a tick of `dt` seconds run with `controls`.
+    pub fn record(&mut self, dt: f64, controls: &Actions) {
+        self.ticks.push(ReplayTick {
+            dt,
+            controls: controls.clone(),
+            weapon_cycles: self.weapon_cycles,
+        });
+        self.weapon_cycles = 0;
+    }
+
+    /// Drops every tick after the first `len`, when the world was rewound to then.
+    pub fn truncate(&mut self, len: usize) {
+        self.ticks.truncate(len);
+        self.weapon_cycles = 0;
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        ron::de::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = to_string_pretty(self, PrettyConfig::new()).map_err(|e| e.to_string())?;
+        fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::Assets;
+    use crate::{load_config, load_waves};
+
+    fn world(seed: u64) -> World {
+        let assets = Assets::new(None);
+        World::new(load_config(None, &assets).unwrap(), load_waves(None, &assets).unwrap(), seed)
+    }
+
+    #[test]
+    fn test_replay_plays_back_the_same() {
+        let mut original = world(8);
+        let mut replay = Replay::new(original.snapshot());
+        for i in 0..400 {
+            if i % 150 == 0 {
+                original.cycle_weapon();
+                replay.cycle_weapon();
+            }
+            let actions = &mut original.ship_mut().actions;
+            actions.rotate_ccw = i % 80 < 25;
+            actions.fire_boosters = i % 100 < 15;
+            actions.is_shooting = i % 5 != 0;
+            replay.record(1.0 / 60.0, &original.ship().actions);
+            original.update(UpdateArgs { dt: 1.0 / 60.0 });
+        }
+        let text = to_string_pretty(&replay, PrettyConfig::new()).unwrap();
+        let replay: Replay = ron::de::from_str(&text).unwrap();
+        let mut played = world(1);
+        played.restore(replay.start.clone());
+        for tick in &replay.ticks {
+            tick.play(&mut played);
+        }
+        let original = ron::ser::to_string(&original.snapshot()).unwrap();
+        assert_eq!(ron::ser::to_string(&played.snapshot()).unwrap(), original);
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::VecDeque;
+
+/// The last few seconds of world states, newest at the back. Each state is
+/// kept with the seconds the tick after it took, so rewinding goes back at
+/// the speed the game was played.
+pub struct Rewind<T> {
+    frames: VecDeque<(f64, T)>,
+    /// Seconds covered by `frames`.
+    span: f64,
+    limit: f64,
+}
+
+impl<T> Rewind<T> {
+    /// Keeps up to `limit` seconds of states.
+    pub fn new(limit: f64) -> Self {
+        Self {
+            frames: VecDeque::new(),
+            span: 0.0,
+            limit,
+        }
+    }
+
+    /// Seconds that can be rewound.
+    pub fn seconds(&self) -> f64 {
+        self.span
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Adds the state a tick of `dt` seconds starts from, dropping the
+    /// oldest ones past the limit.
+    pub fn record(&mut self, dt: f64, state: T) {
+        self.frames.push_back((dt, state));
+        self.span += dt;
+        while self.span > self.limit {
+            match self.frames.pop_front() {
+                Some((dt, _)) => self.span -= dt,
+                None => break,
+            }
+        }
+    }
+
+    /// Goes back `dt` seconds, returning the state to restore, or `None`
+    /// once there is nothing left to rewind.
+    pub fn rewind(&mut self, dt: f64) -> Option<T> {
+        let mut back = 0.0;
+        let mut state = None;
+        while back < dt {
+            let (frame_dt, frame) = match self.frames.pop_back() {
+                Some(frame) => frame,
+                None => break,
+            };
+            back += frame_dt;
+            self.span -= frame_dt;
+            state = Some(frame);
+        }
+        if self.frames.is_empty() {
+            self.span = 0.0;
+        }
+        state
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.span = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oldest_states_are_dropped() {
+        let mut rewind = Rewind::new(1.0);
+        for tick in 0..100 {
+            rewind.record(0.25, tick);
+        }
+        assert_eq!(rewind.seconds(), 1.0);
+        let mut left = Vec::new();
+        while let Some(tick) = rewind.rewind(0.25) {
+            left.push(tick);
+        }
+        assert_eq!(left, vec![99, 98, 97, 96]);
+        assert!(rewind.is_empty());
+    }
+
+    #[test]
+    fn test_rewinds_in_real_time() {
+        let mut rewind = Rewind::new(10.0);
+        for tick in 0..8 {
+            rewind.record(0.25, tick);
+        }
+        // a slow frame goes back several ticks at once
+        assert_eq!(rewind.rewind(0.5), Some(6));
+        assert_eq!(rewind.rewind(0.1), Some(5));
+        assert_eq!(rewind.seconds(), 1.25);
+    }
+}
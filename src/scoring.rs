@@ -0,0 +1,334 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::components::roid::RoidSize;
+use crate::events::{GameEvent, Subscriber};
+
+/// Points for every second a wave is cleared under `par` seconds.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct TimeBonus {
+    pub par: f64,
+    pub per_second: u32,
+}
+
+/// How points are handed out. Left out, a roid is worth its diameter and
+/// there are no combos, bonuses or extra lives, like before.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScoringConfig {
+    /// Points for a large, medium and small roid, their diameter when left out.
+    #[serde(default)]
+    pub roid_points: Option<(u32, u32, u32)>,
+    /// Hits in a row it takes to raise the multiplier by one, 0 turns combos off.
+    #[serde(default)]
+    pub combo_step: u32,
+    #[serde(default = "default_one")]
+    pub max_multiplier: u32,
+    /// Points for clearing a wave without missing, scaled down by accuracy.
+    #[serde(default)]
+    pub accuracy_bonus: u32,
+    #[serde(default)]
+    pub time_bonus: Option<TimeBonus>,
+    #[serde(default)]
+    pub extra_life_every: Option<u32>,
+    /// Lives the ship starts with, losing the last one starts the game over.
+    #[serde(default = "default_one")]
+    pub lives: u32,
+}
+
+fn default_one() -> u32 {
+    1
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        Self {
+            roid_points: None,
+            combo_step: 0,
+            max_multiplier: 1,
+            accuracy_bonus: 0,
+            time_bonus: None,
+            extra_life_every: None,
+            lives: 1,
+        }
+    }
+}
+
+/// Something the HUD can tell the player about.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScoreEvent {
+    /// Points for something destroyed, with the multiplier already applied.
+    Destroyed { points: u32, multiplier: u32 },
+    /// The multiplier went up.
+    Combo { multiplier: u32 },
+    /// A miss or a death ended a combo.
+    ComboBroken,
+    AccuracyBonus { accuracy: f64, points: u32 },
+    TimeBonus { seconds: f64, points: u32 },
+    ExtraLife { lives: u32 },
+}
+
+impl fmt::Display for ScoreEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScoreEvent::Destroyed { points, multiplier: 1 } => write!(f, "+{}", points),
+            ScoreEvent::Destroyed { points, multiplier } => write!(f, "+{} (x{})", points, multiplier),
+            ScoreEvent::Combo { multiplier } => write!(f, "Combo x{}", multiplier),
+            ScoreEvent::ComboBroken => write!(f, "Combo broken"),
+            ScoreEvent::AccuracyBonus { accuracy, points } => {
+                write!(f, "Accuracy {:.0}%: +{}", accuracy * 100.0, points)
+            }
+            ScoreEvent::TimeBonus { seconds, points } => write!(f, "{:.1}s under par: +{}", seconds, points),
+            ScoreEvent::ExtraLife { lives } => write!(f, "Extra life! Lives: {}", lives),
+        }
+    }
+}
+
+/// The score, lives and combo, and the events scoring raised this tick.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Scoring {
+    config: ScoringConfig,
+    pub score: u32,
+    pub lives: u32,
+    combo: u32,
+    /// Shots fired and shots that hit something this wave.
+    shots: u32,
+    hits: u32,
+    #[serde(skip)]
+    events: Vec<ScoreEvent>,
+}
+
+impl Scoring {
+    pub fn new(config: ScoringConfig) -> Self {
+        Self {
+            score: 0,
+            lives: config.lives.max(1),
+            combo: 0,
+            shots: 0,
+            hits: 0,
+            events: Vec::new(),
+            config,
+        }
+    }
+
+    pub fn multiplier(&self) -> u32 {
+        match self.config.combo_step {
+            0 => 1,
+            step => (1 + self.combo / step).min(self.config.max_multiplier.max(1)),
+        }
+    }
+
+    /// Hits this wave over shots fired, `None` before the first shot.
+    pub fn accuracy(&self) -> Option<f64> {
+        if self.shots == 0 {
+            None
+        } else {
+            Some(f64::from(self.hits) / f64::from(self.shots))
+        }
+    }
+
+    /// Events raised since the last `begin_tick`.
+    pub fn events(&self) -> &[ScoreEvent] {
+        &self.events
+    }
+
+    pub fn begin_tick(&mut self) {
+        self.events.clear();
+    }
+
+    /// Points scored since the last `begin_tick`.
+    pub fn points(&self) -> u32 {
+        self.events.iter().map(|event| match event {
+            ScoreEvent::Destroyed { points, .. }
+            | ScoreEvent::AccuracyBonus { points, .. }
+            | ScoreEvent::TimeBonus { points, .. } => *points,
+            _ => 0,
+        }).sum()
+    }
+
+    pub fn fired(&mut self, shots: u32) {
+        self.shots += shots;
+    }
+
+    /// A shot hit something for the first time.
+    pub fn hit(&mut self) {
+        let before = self.multiplier();
+        self.hits += 1;
+        self.combo += 1;
+        let multiplier = self.multiplier();
+        if multiplier > before {
+            self.events.push(ScoreEvent::Combo { multiplier });
+        }
+    }
+
+    /// A shot went away without hitting anything.
+    pub fn miss(&mut self) {
+        self.break_combo();
+    }
+
+    /// Scores a destroyed roid `diameter` px across, returning the points.
+    pub fn roid_destroyed(&mut self, size: RoidSize, diameter: f64) -> u32 {
+        let base = match (self.config.roid_points, size) {
+            (Some((large, _, _)), RoidSize::Large) => large,
+            (Some((_, medium, _)), RoidSize::Medium) => medium,
+            (Some((_, _, small)), RoidSize::Small) => small,
+            (None, _) => diameter.round() as u32,
+        };
+        self.destroyed(base)
+    }
+
+    /// Awards the accuracy and time bonuses for a wave cleared after
+    /// `wave_time` seconds, returning the points.
+    pub fn wave_cleared(&mut self, wave_time: f64) -> u32 {
+        let mut total = 0;
+        if let Some(accuracy) = self.accuracy() {
+            let points = (f64::from(self.config.accuracy_bonus) * accuracy).round() as u32;
+            if points > 0 {
+                self.events.push(ScoreEvent::AccuracyBonus { accuracy, points });
+                total += self.award(points);
+            }
+        }
+        if let Some(bonus) = self.config.time_bonus {
+            let seconds = bonus.par - wave_time;
+            let points = (seconds.max(0.0) * f64::from(bonus.per_second)).round() as u32;
+            if points > 0 {
+                self.events.push(ScoreEvent::TimeBonus { seconds, points });
+                total += self.award(points);
+            }
+        }
+        total
+    }
+
+    /// Resets the per-wave accuracy.
+    pub fn start_wave(&mut self) {
+        self.shots = 0;
+        self.hits = 0;
+    }
+
+    /// Takes a life, returning true when it was the last one and the game
+    /// is over, in which case the score and lives start over.
+    pub fn died(&mut self) -> bool {
+        self.break_combo();
+        self.lives = self.lives.saturating_sub(1);
+        if self.lives > 0 {
+            return false;
+        }
+        self.score = 0;
+        self.lives = self.config.lives.max(1);
+        true
+    }
+
+    fn break_combo(&mut self) {
+        if self.multiplier() > 1 {
+            self.events.push(ScoreEvent::ComboBroken);
+        }
+        self.combo = 0;
+    }
+
+    fn destroyed(&mut self, base: u32) -> u32 {
+        let multiplier = self.multiplier();
+        let points = base * multiplier;
+        self.events.push(ScoreEvent::Destroyed { points, multiplier });
+        self.award(points)
+    }
+
+    /// Adds `points` to the score, handing out a life for every
+    /// `extra_life_every` points crossed.
+    fn award(&mut self, points: u32) -> u32 {
+        let before = self.score;
+        self.score += points;
+        if let Some(every) = self.config.extra_life_every.filter(|&every| every > 0) {
+            for _ in before / every..self.score / every {
+                self.lives += 1;
+                self.events.push(ScoreEvent::ExtraLife { lives: self.lives });
+            }
+        }
+        points
+    }
+}
+
+impl Subscriber for Scoring {
+    fn notify(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::LaserFired { .. } => self.fired(1),
+            GameEvent::ShotMissed => self.miss(),
+            GameEvent::RoidHit { first_hit: true, .. } => self.hit(),
+            GameEvent::RoidDestroyed { size, diameter, .. } => {
+                self.roid_destroyed(size, diameter);
+            }
+            GameEvent::WaveCleared { wave_time, .. } => {
+                self.wave_cleared(wave_time);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ScoringConfig {
+        ScoringConfig {
+            roid_points: Some((20, 50, 100)),
+            combo_step: 2,
+            max_multiplier: 3,
+            accuracy_bonus: 1000,
+            time_bonus: Some(TimeBonus { par: 30.0, per_second: 10 }),
+            extra_life_every: Some(300),
+            lives: 2,
+        }
+    }
+
+    #[test]
+    fn test_default_scores_diameter() {
+        let mut scoring = Scoring::new(ScoringConfig::default());
+        for _ in 0..10 {
+            scoring.hit();
+        }
+        assert_eq!(scoring.roid_destroyed(RoidSize::Large, 49.6), 50);
+        assert_eq!(scoring.wave_cleared(0.0), 0);
+        assert_eq!(scoring.events(), &[ScoreEvent::Destroyed { points: 50, multiplier: 1 }]);
+    }
+
+    #[test]
+    fn test_combo_multiplies_until_a_miss() {
+        let mut scoring = Scoring::new(config());
+        scoring.hit();
+        assert_eq!(scoring.roid_destroyed(RoidSize::Large, 50.0), 20);
+        scoring.hit();
+        assert_eq!(scoring.multiplier(), 2);
+        for _ in 0..10 {
+            scoring.hit();
+        }
+        assert_eq!(scoring.roid_destroyed(RoidSize::Medium, 30.0), 150);
+        scoring.miss();
+        assert_eq!(scoring.multiplier(), 1);
+        let combos: Vec<_> = scoring.events().iter().filter(|event| {
+            matches!(event, ScoreEvent::Combo { .. } | ScoreEvent::ComboBroken)
+        }).collect();
+        assert_eq!(combos, vec![
+            &ScoreEvent::Combo { multiplier: 2 },
+            &ScoreEvent::Combo { multiplier: 3 },
+            &ScoreEvent::ComboBroken,
+        ]);
+    }
+
+    #[test]
+    fn test_wave_bonuses_and_extra_lives() {
+        let mut scoring = Scoring::new(config());
+        scoring.fired(4);
+        scoring.hit();
+        scoring.begin_tick();
+        // 250 for 25% accuracy and 10 seconds under par make 350 and a life
+        assert_eq!(scoring.wave_cleared(20.0), 350);
+        assert_eq!(scoring.lives, 3);
+        assert!(scoring.events().contains(&ScoreEvent::ExtraLife { lives: 3 }));
+        scoring.start_wave();
+        assert_eq!(scoring.accuracy(), None);
+        assert!(!scoring.died());
+        assert!(!scoring.died());
+        assert!(scoring.died());
+        assert_eq!((scoring.score, scoring.lives), (0, 2));
+    }
+}
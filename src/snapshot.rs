@@ -0,0 +1,74 @@
+use std::fs;
+use std::path::Path;
+
+use rand_pcg::Pcg64Mcg;
+use ron::ser::{to_string_pretty, PrettyConfig};
+use serde::{Deserialize, Serialize};
+
+use crate::ecs::{Ecs, Entity};
+use crate::scoring::Scoring;
+use crate::stats::SessionStats;
+use crate::waves::Wave;
+
+/// Everything about a run that isn't in the config or the waves, enough to
+/// carry on exactly where it was taken.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub ecs: Ecs,
+    pub ship: Entity,
+    pub scoring: Scoring,
+    pub stats: SessionStats,
+    pub level: u32,
+    pub wave: Wave,
+    pub wave_time: f64,
+    pub rng: Pcg64Mcg,
+}
+
+impl Snapshot {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        ron::de::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = to_string_pretty(self, PrettyConfig::new()).map_err(|e| e.to_string())?;
+        fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use piston_window::UpdateArgs;
+    use crate::assets::Assets;
+    use crate::world::World;
+    use crate::{load_config, load_waves};
+
+    fn world(seed: u64) -> World {
+        let assets = Assets::new(None);
+        World::new(load_config(None, &assets).unwrap(), load_waves(None, &assets).unwrap(), seed)
+    }
+
+    fn play(world: &mut World, ticks: u32) {
+        for i in 0..ticks {
+            let actions = &mut world.ship_mut().actions;
+            actions.rotate_cw = i % 90 < 30;
+            actions.fire_boosters = i % 120 < 20;
+            actions.is_shooting = i % 7 != 0;
+            world.update(UpdateArgs { dt: 1.0 / 60.0 });
+        }
+    }
+
+    #[test]
+    fn test_restored_world_carries_on_the_same() {
+        let mut original = world(5);
+        play(&mut original, 200);
+        let text = to_string_pretty(&original.snapshot(), PrettyConfig::new()).unwrap();
+        let mut restored = world(99);
+        restored.restore(ron::de::from_str(&text).unwrap());
+        play(&mut original, 300);
+        play(&mut restored, 300);
+        let original = ron::ser::to_string(&original.snapshot()).unwrap();
+        assert_eq!(ron::ser::to_string(&restored.snapshot()).unwrap(), original);
+    }
+}
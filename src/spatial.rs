@@ -0,0 +1,121 @@
+use crate::ecs::{Ecs, Entity};
+use crate::utils::Vector;
+use crate::{VIEW_H, VIEW_W};
+
+/// Roids bucketed by position, so collisions and targeting only look at the
+/// ones nearby. Cells wrap around the playfield like everything else does.
+pub struct SpatialGrid {
+    cell: f64,
+    cols: i64,
+    rows: i64,
+    cells: Vec<Vec<Entity>>,
+    max_radius: f64,
+}
+
+impl SpatialGrid {
+    pub fn new(cell: f64) -> Self {
+        let cols = (VIEW_W / cell).ceil() as i64;
+        let rows = (VIEW_H / cell).ceil() as i64;
+        Self {
+            cell,
+            cols,
+            rows,
+            cells: vec![Vec::new(); (cols * rows) as usize],
+            max_radius: 0.0,
+        }
+    }
+
+    /// Re-buckets every roid, keeping the cells' allocations around.
+    pub fn rebuild(&mut self, ecs: &Ecs) {
+        for cell in &mut self.cells {
+            cell.clear();
+        }
+        self.max_radius = 0.0;
+        for roid in ecs.roids.entities() {
+            let (transform, collider) = match (ecs.transforms.get(roid), ecs.colliders.get(roid)) {
+                (Some(transform), Some(collider)) => (transform, collider),
+                _ => continue,
+            };
+            let index = self.index(self.col(transform.pos.x), self.row(transform.pos.y));
+            self.cells[index].push(roid);
+            self.max_radius = self.max_radius.max(collider.radius);
+        }
+    }
+
+    /// Radius of the largest roid, add it to a query to catch roids whose
+    /// centre is outside the query but whose edge isn't.
+    pub fn max_radius(&self) -> f64 {
+        self.max_radius
+    }
+
+    /// Fills `out` with every roid whose centre may be within `radius` of `pos`.
+    pub fn query(&self, pos: Vector, radius: f64, out: &mut Vec<Entity>) {
+        out.clear();
+        let (first_col, last_col) = (self.col(pos.x - radius), self.col(pos.x + radius));
+        let (first_row, last_row) = (self.row(pos.y - radius), self.row(pos.y + radius));
+        let last_col = last_col.min(first_col + self.cols - 1);
+        let last_row = last_row.min(first_row + self.rows - 1);
+        for row in first_row..=last_row {
+            for col in first_col..=last_col {
+                out.extend_from_slice(&self.cells[self.index(col, row)]);
+            }
+        }
+    }
+
+    fn col(&self, x: f64) -> i64 {
+        (x / self.cell).floor() as i64
+    }
+
+    fn row(&self, y: f64) -> i64 {
+        (y / self.cell).floor() as i64
+    }
+
+    fn index(&self, col: i64, row: i64) -> usize {
+        (row.rem_euclid(self.rows) * self.cols + col.rem_euclid(self.cols)) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{Collider, Transform};
+    use crate::components::roid::{Roid, RoidSize};
+
+    fn roid(ecs: &mut Ecs, x: f64, y: f64) -> Entity {
+        let entity = ecs.spawn();
+        ecs.transforms.insert(entity, Transform { pos: Vector::new(x, y), rot: 0.0 });
+        ecs.colliders.insert(entity, Collider { radius: 10.0 });
+        ecs.roids.insert(entity, Roid { size: RoidSize::Large, health: 1 });
+        entity
+    }
+
+    #[test]
+    fn test_query_finds_nearby_roids() {
+        let mut ecs = Ecs::new();
+        let near = roid(&mut ecs, 100.0, 100.0);
+        let far = roid(&mut ecs, 600.0, 500.0);
+        let mut grid = SpatialGrid::new(64.0);
+        grid.rebuild(&ecs);
+        let mut out = Vec::new();
+        grid.query(Vector::new(120.0, 90.0), 30.0, &mut out);
+        assert_eq!(out, vec![near]);
+        grid.query(Vector::new(400.0, 400.0), 1000.0, &mut out);
+        assert!(out.contains(&near) && out.contains(&far));
+        assert_eq!(out.len(), 2);
+        assert_eq!(grid.max_radius(), 10.0);
+    }
+
+    #[test]
+    fn test_query_wraps_around_edges() {
+        let mut ecs = Ecs::new();
+        let edge = roid(&mut ecs, VIEW_W - 5.0, 100.0);
+        let offscreen = roid(&mut ecs, -8.0, 400.0);
+        let mut grid = SpatialGrid::new(64.0);
+        grid.rebuild(&ecs);
+        let mut out = Vec::new();
+        grid.query(Vector::new(5.0, 100.0), 20.0, &mut out);
+        assert_eq!(out, vec![edge]);
+        grid.query(Vector::new(-5.0, 400.0), 10.0, &mut out);
+        assert_eq!(out, vec![offscreen]);
+    }
+}
@@ -0,0 +1,266 @@
+use std::fs;
+use std::path::PathBuf;
+
+use ron::ser::{to_string_pretty, PrettyConfig};
+use serde::{Deserialize, Serialize};
+
+use crate::components::roid::RoidSize;
+use crate::events::{GameEvent, Subscriber};
+
+/// Counters for one game, from the first wave until the last life is lost.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct SessionStats {
+    pub score: u32,
+    pub shots_fired: u32,
+    pub hits: u32,
+    /// Large, medium and small roids destroyed.
+    pub roids_destroyed: (u32, u32, u32),
+    /// px the ship flew.
+    pub distance: f64,
+    /// Seconds the ship spent alive.
+    pub time_alive: f64,
+    pub deaths: u32,
+    pub waves_cleared: u32,
+}
+
+impl SessionStats {
+    pub fn accuracy(&self) -> Option<f64> {
+        if self.shots_fired == 0 {
+            None
+        } else {
+            Some(f64::from(self.hits) / f64::from(self.shots_fired))
+        }
+    }
+
+    pub fn roid_destroyed(&mut self, size: RoidSize) {
+        match size {
+            RoidSize::Large => self.roids_destroyed.0 += 1,
+            RoidSize::Medium => self.roids_destroyed.1 += 1,
+            RoidSize::Small => self.roids_destroyed.2 += 1,
+        }
+    }
+
+    pub fn total_roids(&self) -> u32 {
+        let (large, medium, small) = self.roids_destroyed;
+        large + medium + small
+    }
+
+    fn add(&mut self, other: &SessionStats) {
+        self.score += other.score;
+        self.shots_fired += other.shots_fired;
+        self.hits += other.hits;
+        self.roids_destroyed.0 += other.roids_destroyed.0;
+        self.roids_destroyed.1 += other.roids_destroyed.1;
+        self.roids_destroyed.2 += other.roids_destroyed.2;
+        self.distance += other.distance;
+        self.time_alive += other.time_alive;
+        self.deaths += other.deaths;
+        self.waves_cleared += other.waves_cleared;
+    }
+
+    /// The stats as lines of text for the game over screen.
+    pub fn lines(&self) -> Vec<String> {
+        let accuracy = self.accuracy().map_or("-".to_string(), |accuracy| format!("{:.0}%", accuracy * 100.0));
+        let (large, medium, small) = self.roids_destroyed;
+        vec![
+            format!("Score: {}  Waves cleared: {}", self.score, self.waves_cleared),
+            format!("Shots fired: {}  Hits: {}  Accuracy: {}", self.shots_fired, self.hits, accuracy),
+            format!("Roids destroyed: {} large, {} medium, {} small", large, medium, small),
+            format!("Distance: {:.0} px  Time alive: {:.0}s  Deaths: {}", self.distance, self.time_alive, self.deaths),
+        ]
+    }
+}
+
+impl Subscriber for SessionStats {
+    fn notify(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::LaserFired { .. } => self.shots_fired += 1,
+            GameEvent::RoidHit { first_hit: true, .. } => self.hits += 1,
+            GameEvent::RoidDestroyed { size, .. } => self.roid_destroyed(size),
+            GameEvent::WaveCleared { .. } => self.waves_cleared += 1,
+            GameEvent::ShipKilled { .. } => self.deaths += 1,
+            _ => {}
+        }
+    }
+}
+
+/// A counter achievements can be unlocked by.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Counter {
+    Score,
+    ShotsFired,
+    Hits,
+    /// Between 0 and 1.
+    Accuracy,
+    RoidsDestroyed,
+    LargeRoidsDestroyed,
+    MediumRoidsDestroyed,
+    SmallRoidsDestroyed,
+    Distance,
+    TimeAlive,
+    Deaths,
+    WavesCleared,
+    Games,
+}
+
+impl Counter {
+    fn value(self, stats: &SessionStats, games: u32) -> f64 {
+        match self {
+            Counter::Score => f64::from(stats.score),
+            Counter::ShotsFired => f64::from(stats.shots_fired),
+            Counter::Hits => f64::from(stats.hits),
+            Counter::Accuracy => stats.accuracy().unwrap_or(0.0),
+            Counter::RoidsDestroyed => f64::from(stats.total_roids()),
+            Counter::LargeRoidsDestroyed => f64::from(stats.roids_destroyed.0),
+            Counter::MediumRoidsDestroyed => f64::from(stats.roids_destroyed.1),
+            Counter::SmallRoidsDestroyed => f64::from(stats.roids_destroyed.2),
+            Counter::Distance => stats.distance,
+            Counter::TimeAlive => stats.time_alive,
+            Counter::Deaths => f64::from(stats.deaths),
+            Counter::WavesCleared => f64::from(stats.waves_cleared),
+            Counter::Games => f64::from(games),
+        }
+    }
+}
+
+/// Unlocked once `counter` reaches `at_least`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Achievement {
+    pub name: String,
+    pub description: String,
+    pub counter: Counter,
+    pub at_least: f64,
+    /// Checked against a single game instead of the lifetime totals.
+    #[serde(default)]
+    pub in_one_game: bool,
+}
+
+impl Achievement {
+    pub fn reached(&self, session: &SessionStats, lifetime: &LifetimeStats) -> bool {
+        let value = if self.in_one_game {
+            self.counter.value(session, 1)
+        } else {
+            self.counter.value(&lifetime.totals, lifetime.games)
+        };
+        value >= self.at_least
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Achievements {
+    pub achievements: Vec<Achievement>,
+}
+
+/// Every game played, added up.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct LifetimeStats {
+    pub games: u32,
+    pub best_score: u32,
+    pub totals: SessionStats,
+    /// Names of the achievements unlocked so far.
+    pub unlocked: Vec<String>,
+}
+
+impl LifetimeStats {
+    /// Adds a finished game, returning the achievements it unlocked.
+    pub fn record<'a>(&mut self, session: &SessionStats, achievements: &'a [Achievement]) -> Vec<&'a Achievement> {
+        self.games += 1;
+        self.best_score = self.best_score.max(session.score);
+        self.totals.add(session);
+        let mut unlocked = Vec::new();
+        for achievement in achievements {
+            if !self.unlocked.contains(&achievement.name) && achievement.reached(session, self) {
+                self.unlocked.push(achievement.name.clone());
+                unlocked.push(achievement);
+            }
+        }
+        unlocked
+    }
+}
+
+/// Lifetime stats kept in a file between runs, and the achievements to unlock.
+pub struct Records {
+    path: PathBuf,
+    pub lifetime: LifetimeStats,
+    achievements: Vec<Achievement>,
+}
+
+impl Records {
+    /// Reads the lifetime stats from `path`, starting fresh if it doesn't exist yet.
+    pub fn load(path: PathBuf, achievements: Achievements) -> Result<Self, String> {
+        let lifetime = if path.exists() {
+            let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            ron::de::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?
+        } else {
+            LifetimeStats::default()
+        };
+        Ok(Self {
+            path,
+            lifetime,
+            achievements: achievements.achievements,
+        })
+    }
+
+    /// Records a finished game and saves the lifetime stats, returning the
+    /// achievements it unlocked.
+    pub fn finish_game(&mut self, session: &SessionStats) -> Vec<&Achievement> {
+        let unlocked = self.lifetime.record(session, &self.achievements);
+        let saved = to_string_pretty(&self.lifetime, PrettyConfig::new())
+            .map_err(|e| e.to_string())
+            .and_then(|text| fs::write(&self.path, text).map_err(|e| e.to_string()));
+        if let Err(e) = saved {
+            println!("Failed to save stats to {}: {}", self.path.display(), e);
+        }
+        unlocked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn achievement(name: &str, counter: Counter, at_least: f64, in_one_game: bool) -> Achievement {
+        Achievement {
+            name: name.to_string(),
+            description: String::new(),
+            counter,
+            at_least,
+            in_one_game,
+        }
+    }
+
+    #[test]
+    fn test_achievements_unlock_once() {
+        let achievements = vec![
+            achievement("hundred", Counter::RoidsDestroyed, 100.0, false),
+            achievement("sharpshooter", Counter::Accuracy, 0.9, true),
+        ];
+        let mut lifetime = LifetimeStats::default();
+        let mut session = SessionStats { shots_fired: 10, hits: 9, ..SessionStats::default() };
+        session.roids_destroyed = (20, 20, 20);
+        let names: Vec<&str> = lifetime.record(&session, &achievements).iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["sharpshooter"]);
+        let names: Vec<&str> = lifetime.record(&session, &achievements).iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["hundred"]);
+        assert_eq!(lifetime.games, 2);
+        assert_eq!(lifetime.totals.total_roids(), 120);
+        assert_eq!(lifetime.unlocked, vec!["sharpshooter", "hundred"]);
+    }
+
+    #[test]
+    fn test_lifetime_stats_round_trip() {
+        let mut lifetime = LifetimeStats::default();
+        let session = SessionStats { score: 1200, distance: 3500.5, deaths: 3, ..SessionStats::default() };
+        lifetime.record(&session, &[]);
+        let text = to_string_pretty(&lifetime, PrettyConfig::new()).unwrap();
+        assert_eq!(ron::de::from_str::<LifetimeStats>(&text).unwrap(), lifetime);
+    }
+
+    #[test]
+    fn test_achievements_file_parses() {
+        let achievements: Achievements = ron::de::from_str(include_str!("../achievements.ron")).unwrap();
+        assert!(!achievements.achievements.is_empty());
+    }
+}
@@ -0,0 +1,374 @@
+use crate::components::well::total_pull;
+use crate::ecs::{Ecs, Entity};
+use crate::render::Renderer;
+use crate::spatial::SpatialGrid;
+use crate::utils::{Vector, loop_pos, wrapped_offset};
+use crate::{VIEW_H, VIEW_W};
+
+/// Burns missile fuel steering each missile towards its target, locking on
+/// to a new one through `grid` whenever it has none.
+pub fn steer_missiles(ecs: &mut Ecs, grid: &SpatialGrid, dt: f64) {
+    let Ecs { missiles, roids, transforms, bodies, .. } = ecs;
+    if missiles.is_empty() {
+        return;
+    }
+    let mut candidates = Vec::new();
+    for (entity, missile) in missiles.iter_mut() {
+        if missile.fuel <= 0.0 {
+            continue;
+        }
+        missile.fuel -= dt;
+        let (pos, heading) = match (transforms.get(entity), bodies.get(entity)) {
+            (Some(transform), Some(body)) => (transform.pos, body.vel.angle()),
+            _ => continue,
+        };
+        if !missile.target.is_some_and(|target| roids.contains(target)) {
+            grid.query(pos, missile.range + grid.max_radius(), &mut candidates);
+            missile.target = candidates
+                .iter()
+                .filter(|roid| roids.contains(**roid))
+                .filter_map(|roid| {
+                    let offset = wrapped_offset(pos, transforms.get(*roid)?.pos, Vector::new(VIEW_W, VIEW_H));
+                    let in_cone = angle_between(heading, offset.angle()).abs() <= missile.cone;
+                    if in_cone && offset.length() <= missile.range {
+                        Some((*roid, offset.length()))
+                    } else {
+                        None
+                    }
+                })
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                .map(|(roid, _)| roid);
+        }
+        let target = match missile.target.and_then(|target| transforms.get(target)) {
+            Some(transform) => transform.pos,
+            None => continue,
+        };
+        let offset = wrapped_offset(pos, target, Vector::new(VIEW_W, VIEW_H));
+        let max_turn = missile.turn_rate * dt;
+        let turn = angle_between(heading, offset.angle()).max(-max_turn).min(max_turn);
+        if let (Some(body), Some(transform)) = (bodies.get_mut(entity), transforms.get_mut(entity)) {
+            body.vel = body.vel.rotate(turn);
+            transform.rot = body.vel.angle();
+        }
+    }
+}
+
+/// Signed difference from `from` to `to` in degrees, in -180..180.
+fn angle_between(from: f64, to: f64) -> f64 {
+    (to - from + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Pulls every body towards the wells.
+pub fn gravity(ecs: &mut Ecs, dt: f64) {
+    if ecs.wells.is_empty() {
+        return;
+    }
+    let wells: Vec<_> = ecs
+        .wells
+        .iter()
+        .filter_map(|(entity, well)| ecs.transforms.get(entity).map(|t| (t.pos, *well)))
+        .collect();
+    for (entity, body) in ecs.bodies.iter_mut() {
+        if let Some(transform) = ecs.transforms.get(entity) {
+            body.vel += total_pull(&wells, transform.pos) * dt;
+        }
+    }
+}
+
+/// Moves and spins every body, wrapping it around the edges of the
+/// playfield or despawning it there if it is `Bounded`.
+pub fn integrate(ecs: &mut Ecs, dt: f64) {
+    let mut escaped = Vec::new();
+    for (entity, body) in ecs.bodies.iter() {
+        if let Some(transform) = ecs.transforms.get_mut(entity) {
+            transform.pos += body.vel * (dt * 60.0);
+            transform.rot += body.rot_vel * dt;
+            let wrapped = loop_pos(transform.pos, body.wrap_margin, Vector::new(VIEW_W, VIEW_H));
+            if wrapped != transform.pos && ecs.bounded.contains(entity) {
+                escaped.push(entity);
+            }
+            transform.pos = wrapped;
+        }
+    }
+    for entity in escaped {
+        ecs.despawn(entity);
+    }
+}
+
+/// Counts down lifetimes and despawns whatever ran out.
+pub fn age(ecs: &mut Ecs, dt: f64) {
+    let mut expired = Vec::new();
+    for (entity, lifetime) in ecs.lifetimes.iter_mut() {
+        lifetime.remaining = (lifetime.remaining - dt).max(0.0);
+        if lifetime.remaining == 0.0 {
+            expired.push(entity);
+        }
+    }
+    for entity in expired {
+        ecs.despawn(entity);
+    }
+}
+
+/// Despawns everything that crossed a black hole's event horizon, apart from
+/// ships, which are returned so the caller can decide what happens to them.
+pub fn swallow(ecs: &mut Ecs) -> Vec<Entity> {
+    let mut swallowed = Vec::new();
+    for (well_entity, well) in ecs.wells.iter() {
+        let center = match ecs.transforms.get(well_entity) {
+            Some(transform) => transform.pos,
+            None => continue,
+        };
+        for (entity, _) in ecs.bodies.iter() {
+            let inside = ecs.transforms.get(entity).is_some_and(|t| well.swallows(center, t.pos));
+            if inside && !swallowed.contains(&entity) {
+                swallowed.push(entity);
+            }
+        }
+    }
+    let mut ships = Vec::new();
+    for entity in swallowed {
+        if ecs.ships.contains(entity) {
+            ships.push(entity);
+        } else {
+            ecs.despawn(entity);
+        }
+    }
+    ships
+}
+
+/// A roid as far as bouncing is concerned.
+struct Disc {
+    entity: Entity,
+    pos: Vector,
+    vel: Vector,
+    radius: f64,
+    mass: f64,
+}
+
+/// Bounces every overlapping pair of roids off each other and returns the
+/// number of pairs checked.
+pub fn bounce_roids(ecs: &mut Ecs, restitution: f64) -> u32 {
+    let mut discs: Vec<Disc> = ecs
+        .roids
+        .entities()
+        .filter_map(|entity| {
+            let body = ecs.bodies.get(entity)?;
+            Some(Disc {
+                entity,
+                pos: ecs.transforms.get(entity)?.pos,
+                vel: body.vel,
+                radius: ecs.colliders.get(entity)?.radius,
+                mass: body.mass,
+            })
+        })
+        .collect();
+    let mut checks = 0;
+    for i in 1..discs.len() {
+        let (head, tail) = discs.split_at_mut(i);
+        let b = &mut tail[0];
+        for a in head.iter_mut() {
+            checks += 1;
+            bounce(a, b, restitution);
+        }
+    }
+    for disc in discs {
+        if let Some(transform) = ecs.transforms.get_mut(disc.entity) {
+            transform.pos = disc.pos;
+        }
+        if let Some(body) = ecs.bodies.get_mut(disc.entity) {
+            body.vel = disc.vel;
+        }
+    }
+    checks
+}
+
+/// Resolves a collision between `a` and `b`, if they touch, by pushing them
+/// apart and exchanging momentum along the line between their centres.
+fn bounce(a: &mut Disc, b: &mut Disc, restitution: f64) {
+    // each roid wraps `diameter` past the edge, so a pair wraps at the mean of the two
+    let margin = 2.0 * (a.radius + b.radius);
+    let offset = wrapped_offset(a.pos, b.pos, Vector::new(VIEW_W + margin, VIEW_H + margin));
+    let distance = offset.length();
+    let reach = a.radius + b.radius;
+    if distance >= reach || distance == 0.0 {
+        return;
+    }
+    let normal = offset / distance;
+    let total = a.mass + b.mass;
+
+    let overlap = reach - distance;
+    a.pos = loop_pos(a.pos - normal * (overlap * b.mass / total), a.radius * 2.0, Vector::new(VIEW_W, VIEW_H));
+    b.pos = loop_pos(b.pos + normal * (overlap * a.mass / total), b.radius * 2.0, Vector::new(VIEW_W, VIEW_H));
+
+    let closing = (b.vel - a.vel).dot(normal);
+    if closing >= 0.0 {
+        return;
+    }
+    let impulse = -(1.0 + restitution) * closing / (1.0 / a.mass + 1.0 / b.mass);
+    a.vel -= normal * (impulse / a.mass);
+    b.vel += normal * (impulse / b.mass);
+}
+
+/// Draws the wells underneath, then every sprite.
+pub fn render<R: Renderer>(ecs: &Ecs, renderer: &mut R) {
+    for (entity, well) in ecs.wells.iter() {
+        if let Some(transform) = ecs.transforms.get(entity) {
+            well.draw(transform, renderer);
+        }
+    }
+    for (entity, renderable) in ecs.renderables.iter() {
+        if let Some(transform) = ecs.transforms.get(entity) {
+            renderable.draw(transform, renderer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{Body, Collider, Lifetime, Transform};
+    use crate::components::projectile::{Bounded, Missile};
+    use crate::components::roid::{Roid, RoidSize};
+
+    fn roid(ecs: &mut Ecs, x: f64, y: f64, vel: Vector, diameter: f64) -> Entity {
+        let entity = ecs.spawn();
+        ecs.transforms.insert(entity, Transform { pos: Vector::new(x, y), rot: 0.0 });
+        ecs.bodies.insert(entity, Body {
+            vel,
+            rot_vel: 0.0,
+            mass: diameter * diameter,
+            wrap_margin: diameter,
+        });
+        ecs.colliders.insert(entity, Collider { radius: diameter / 2.0 });
+        ecs.roids.insert(entity, Roid { size: RoidSize::Large, health: 1 });
+        entity
+    }
+
+    fn vel(ecs: &Ecs, entity: Entity) -> Vector {
+        ecs.bodies.get(entity).unwrap().vel
+    }
+
+    fn momentum(ecs: &Ecs) -> Vector {
+        ecs.bodies.iter().fold(Vector::new_empty(), |sum, (_, b)| sum + b.vel * b.mass)
+    }
+
+    #[test]
+    fn test_equal_roids_swap_velocities() {
+        let mut ecs = Ecs::new();
+        let a = roid(&mut ecs, 100.0, 100.0, Vector::new(1.0, 0.0), 50.0);
+        let b = roid(&mut ecs, 140.0, 100.0, Vector::new(-1.0, 0.0), 50.0);
+        assert_eq!(bounce_roids(&mut ecs, 1.0), 1);
+        assert_eq!(vel(&ecs, a), Vector::new(-1.0, 0.0));
+        assert_eq!(vel(&ecs, b), Vector::new(1.0, 0.0));
+        let gap = ecs.transforms.get(a).unwrap().pos.distance(ecs.transforms.get(b).unwrap().pos);
+        assert!(gap >= 50.0 - 1e-9);
+    }
+
+    #[test]
+    fn test_bounce_keeps_momentum() {
+        let mut ecs = Ecs::new();
+        roid(&mut ecs, 100.0, 100.0, Vector::new(2.0, 0.5), 80.0);
+        roid(&mut ecs, 150.0, 130.0, Vector::new(-1.0, 0.0), 30.0);
+        let before = momentum(&ecs);
+        bounce_roids(&mut ecs, 0.5);
+        assert!(before.distance(momentum(&ecs)) < 1e-6);
+    }
+
+    #[test]
+    fn test_inelastic_roids_move_together() {
+        let mut ecs = Ecs::new();
+        let a = roid(&mut ecs, 100.0, 100.0, Vector::new(1.0, 0.0), 50.0);
+        let b = roid(&mut ecs, 140.0, 100.0, Vector::new(-1.0, 0.0), 50.0);
+        bounce_roids(&mut ecs, 0.0);
+        assert!(vel(&ecs, a).length() < 1e-9 && vel(&ecs, b).length() < 1e-9);
+    }
+
+    #[test]
+    fn test_bounce_across_the_edge() {
+        let mut ecs = Ecs::new();
+        let a = roid(&mut ecs, VIEW_W + 40.0, 100.0, Vector::new(1.0, 0.0), 50.0);
+        let b = roid(&mut ecs, -40.0, 100.0, Vector::new(-1.0, 0.0), 50.0);
+        bounce_roids(&mut ecs, 1.0);
+        assert_eq!(vel(&ecs, a), Vector::new(-1.0, 0.0));
+        assert_eq!(vel(&ecs, b), Vector::new(1.0, 0.0));
+    }
+
+    fn missile(ecs: &mut Ecs, vel: Vector) -> Entity {
+        let entity = ecs.spawn();
+        ecs.transforms.insert(entity, Transform::default());
+        ecs.bodies.insert(entity, Body { vel, ..Body::default() });
+        ecs.missiles.insert(entity, Missile {
+            turn_rate: 60.0,
+            cone: 100.0,
+            range: 300.0,
+            fuel: 1.0,
+            blast_radius: 50.0,
+            target: None,
+        });
+        entity
+    }
+
+    #[test]
+    fn test_missile_locks_on_and_turns() {
+        let mut ecs = Ecs::new();
+        let target = roid(&mut ecs, 100.0, 0.0, Vector::new_empty(), 20.0);
+        // behind the missile, outside its cone
+        roid(&mut ecs, 0.0, -50.0, Vector::new_empty(), 20.0);
+        let missile = missile(&mut ecs, Vector::new(0.0, 5.0));
+        let mut grid = SpatialGrid::new(64.0);
+        grid.rebuild(&ecs);
+        steer_missiles(&mut ecs, &grid, 0.5);
+        assert_eq!(ecs.missiles.get(missile).unwrap().target, Some(target));
+        // 30 degrees of the 90 needed to face the roid
+        let vel = vel(&ecs, missile);
+        assert!((vel.angle() - 60.0).abs() < 1e-9);
+        assert!((vel.length() - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_missile_out_of_fuel_coasts() {
+        let mut ecs = Ecs::new();
+        roid(&mut ecs, 100.0, 0.0, Vector::new_empty(), 20.0);
+        let missile = missile(&mut ecs, Vector::new(0.0, 5.0));
+        ecs.missiles.get_mut(missile).unwrap().fuel = 0.0;
+        let mut grid = SpatialGrid::new(64.0);
+        grid.rebuild(&ecs);
+        steer_missiles(&mut ecs, &grid, 0.5);
+        assert_eq!(vel(&ecs, missile), Vector::new(0.0, 5.0));
+        assert_eq!(ecs.missiles.get(missile).unwrap().target, None);
+    }
+
+    #[test]
+    fn test_bounded_bodies_die_at_the_edge() {
+        let mut ecs = Ecs::new();
+        let wrapping = roid(&mut ecs, VIEW_W + 49.0, 100.0, Vector::new(1.0, 0.0), 50.0);
+        let bounded = roid(&mut ecs, VIEW_W + 49.0, 200.0, Vector::new(1.0, 0.0), 50.0);
+        ecs.bounded.insert(bounded, Bounded);
+        integrate(&mut ecs, 1.0 / 60.0);
+        assert!(ecs.entities.is_alive(wrapping));
+        assert!(!ecs.entities.is_alive(bounded));
+    }
+
+    #[test]
+    fn test_absorb_keeps_momentum() {
+        let mut body = Body { vel: Vector::new(1.0, 0.0), mass: 2500.0, ..Body::default() };
+        let before = body.vel * body.mass + Vector::new(0.0, 20.0) * 500.0;
+        body.absorb(Vector::new(0.0, 20.0), 500.0);
+        assert!(before.distance(body.vel * (body.mass + 500.0)) < 1e-6);
+        assert!(body.vel.y > 0.0 && body.vel.x < 1.0);
+    }
+
+    #[test]
+    fn test_integrate_wraps_and_age_despawns() {
+        let mut ecs = Ecs::new();
+        let entity = roid(&mut ecs, VIEW_W + 49.0, 100.0, Vector::new(1.0, 0.0), 50.0);
+        ecs.lifetimes.insert(entity, Lifetime { remaining: 0.02 });
+        integrate(&mut ecs, 1.0 / 60.0);
+        assert_eq!(ecs.transforms.get(entity).unwrap().pos, Vector::new(-50.0, 100.0));
+        age(&mut ecs, 1.0 / 60.0);
+        assert!(ecs.entities.is_alive(entity));
+        age(&mut ecs, 1.0 / 60.0);
+        assert!(!ecs.entities.is_alive(entity));
+        assert!(ecs.roids.is_empty());
+    }
+}
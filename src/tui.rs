@@ -0,0 +1,230 @@
+use std::io::{self, Stdout, Write};
+use std::time::Instant;
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::style::Print;
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use piston_window::Key;
+
+/// Seconds a key counts as held after it was first pressed, long enough to
+/// cover the delay before the terminal starts repeating it.
+const FIRST_HOLD: f64 = 0.5;
+/// Seconds a key counts as held after each repeat.
+const REPEAT_HOLD: f64 = 0.1;
+
+/// What the player did at the keyboard.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Input {
+    Press(Key),
+    Release(Key),
+    Quit,
+}
+
+/// The terminal in raw mode on the alternate screen, put back the way it
+/// was when dropped.
+pub struct Terminal {
+    out: Stdout,
+    /// The terminal reports key releases, so keys don't need to time out.
+    releases: bool,
+}
+
+impl Terminal {
+    pub fn new() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let mut out = io::stdout();
+        execute!(out, EnterAlternateScreen, Hide)?;
+        let releases = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if releases {
+            execute!(out, PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES))?;
+        }
+        Ok(Self { out, releases })
+    }
+
+    pub fn reports_releases(&self) -> bool {
+        self.releases
+    }
+
+    /// Columns and rows.
+    pub fn size(&self) -> io::Result<(usize, usize)> {
+        let (cols, rows) = terminal::size()?;
+        Ok((usize::from(cols), usize::from(rows)))
+    }
+
+    /// Everything typed until `deadline`.
+    pub fn inputs(&mut self, deadline: Instant) -> io::Result<Vec<Input>> {
+        let mut inputs = Vec::new();
+        while event::poll(deadline.saturating_duration_since(Instant::now()))? {
+            if let Event::Key(key) = event::read()? {
+                inputs.extend(input(key));
+            }
+        }
+        Ok(inputs)
+    }
+
+    /// Replaces the screen with `lines`.
+    pub fn draw(&mut self, lines: &[String]) -> io::Result<()> {
+        queue!(self.out, MoveTo(0, 0), Clear(ClearType::All))?;
+        for (row, line) in lines.iter().enumerate() {
+            queue!(self.out, MoveTo(0, row as u16), Print(line))?;
+        }
+        self.out.flush()
+    }
+
+    /// Rings the terminal bell, the only sound a terminal is sure to make.
+    pub fn bell(&mut self) -> io::Result<()> {
+        execute!(self.out, Print('\u{7}'))
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        if self.releases {
+            let _ = execute!(self.out, PopKeyboardEnhancementFlags);
+        }
+        let _ = execute!(self.out, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn input(event: KeyEvent) -> Option<Input> {
+    let ctrl_c = event.code == KeyCode::Char('c') && event.modifiers.contains(KeyModifiers::CONTROL);
+    if event.code == KeyCode::Esc || ctrl_c {
+        return Some(Input::Quit);
+    }
+    let key = key(event.code)?;
+    match event.kind {
+        KeyEventKind::Release => Some(Input::Release(key)),
+        KeyEventKind::Press | KeyEventKind::Repeat => Some(Input::Press(key)),
+    }
+}
+
+/// The window key a terminal key matches, so both use the same key config.
+pub fn key(code: KeyCode) -> Option<Key> {
+    let key = match code {
+        KeyCode::Char(c) if c.is_ascii() => Key::from(u32::from(c.to_ascii_lowercase())),
+        KeyCode::Enter => Key::Return,
+        KeyCode::Tab => Key::Tab,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::F(1) => Key::F1,
+        KeyCode::F(2) => Key::F2,
+        KeyCode::F(3) => Key::F3,
+        KeyCode::F(4) => Key::F4,
+        KeyCode::F(5) => Key::F5,
+        KeyCode::F(6) => Key::F6,
+        KeyCode::F(7) => Key::F7,
+        KeyCode::F(8) => Key::F8,
+        KeyCode::F(9) => Key::F9,
+        KeyCode::F(10) => Key::F10,
+        KeyCode::F(11) => Key::F11,
+        KeyCode::F(12) => Key::F12,
+        _ => return None,
+    };
+    if key == Key::Unknown {
+        None
+    } else {
+        Some(key)
+    }
+}
+
+/// False for modifiers, terminals only send them along with another key.
+pub fn can_press(key: Key) -> bool {
+    !matches!(
+        key,
+        Key::LShift | Key::RShift | Key::LCtrl | Key::RCtrl | Key::LAlt | Key::RAlt | Key::LGui | Key::RGui
+    )
+}
+
+/// Keys held down. Most terminals only report presses, repeating them while
+/// a key stays down, so without releases a key is let go once it stops
+/// repeating.
+pub struct HeldKeys {
+    keys: Vec<(Key, f64)>,
+    releases: bool,
+}
+
+impl HeldKeys {
+    /// `releases` is whether the terminal reports key releases.
+    pub fn new(releases: bool) -> Self {
+        Self {
+            keys: Vec::new(),
+            releases,
+        }
+    }
+
+    /// Returns true when `key` wasn't already held, so this is a new press
+    /// rather than a repeat.
+    pub fn press(&mut self, key: Key) -> bool {
+        let hold = if self.releases { f64::INFINITY } else { REPEAT_HOLD };
+        match self.keys.iter_mut().find(|(held, _)| *held == key) {
+            Some((_, remaining)) => {
+                *remaining = remaining.max(hold);
+                false
+            }
+            None => {
+                self.keys.push((key, hold.max(FIRST_HOLD)));
+                true
+            }
+        }
+    }
+
+    /// Returns true when `key` was held.
+    pub fn release(&mut self, key: Key) -> bool {
+        let before = self.keys.len();
+        self.keys.retain(|(held, _)| *held != key);
+        self.keys.len() < before
+    }
+
+    /// Lets `dt` seconds pass, returning the keys that stopped repeating.
+    pub fn update(&mut self, dt: f64) -> Vec<Key> {
+        let mut released = Vec::new();
+        self.keys.retain_mut(|(key, remaining)| {
+            *remaining -= dt;
+            if *remaining > 0.0 {
+                true
+            } else {
+                released.push(*key);
+                false
+            }
+        });
+        released
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_are_held_while_repeating() {
+        let mut held = HeldKeys::new(false);
+        assert!(held.press(Key::A));
+        assert!(held.update(0.4).is_empty());
+        // the terminal starts repeating
+        for _ in 0..10 {
+            assert!(!held.press(Key::A));
+            assert!(held.update(0.05).is_empty());
+        }
+        assert!(held.update(0.2).contains(&Key::A));
+        assert!(held.press(Key::A));
+        assert!(held.release(Key::A));
+        assert!(!held.release(Key::A));
+    }
+
+    #[test]
+    fn test_terminal_keys_match_window_keys() {
+        assert_eq!(key(KeyCode::Char('W')), Some(Key::W));
+        assert_eq!(key(KeyCode::Char(' ')), Some(Key::Space));
+        assert_eq!(key(KeyCode::Backspace), Some(Key::Backspace));
+        assert_eq!(key(KeyCode::F(5)), Some(Key::F5));
+        assert_eq!(key(KeyCode::Char('é')), None);
+    }
+}
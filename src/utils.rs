@@ -1,8 +1,9 @@
-use std::ops::{Add, AddAssign, Mul, MulAssign, Div, DivAssign, Rem, RemAssign, Sub, SubAssign};
+use std::ops::{Add, AddAssign, Mul, MulAssign, Div, DivAssign, Neg, Rem, RemAssign, Sub, SubAssign};
+use serde::{Deserialize, Serialize};
 
 pub const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
 
-#[derive(Copy, Clone, Default)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Vector {
     pub x: f64,
     pub y: f64,
@@ -39,6 +40,55 @@ impl Vector {
             y: self.y.round(),
         }
     }
+
+    pub fn length(&self) -> f64 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn length_squared(&self) -> f64 {
+        self.dot(*self)
+    }
+
+    /// Unit vector in the same direction, the zero vector stays zero.
+    pub fn normalize(&self) -> Vector {
+        let length = self.length();
+        if length == 0.0 {
+            return *self;
+        }
+        *self / length
+    }
+
+    pub fn dot(&self, other: Vector) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// Z component of the 3d cross product, positive when `other` is clockwise
+    /// of `self` on screen.
+    pub fn cross(&self, other: Vector) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// Rotated clockwise on screen by `degrees`, matching sprite rotation.
+    pub fn rotate(&self, degrees: f64) -> Vector {
+        let (sin, cos) = degree_to_radians(degrees).sin_cos();
+        Vector {
+            x: self.x * cos - self.y * sin,
+            y: self.x * sin + self.y * cos,
+        }
+    }
+
+    /// Angle from the x axis in degrees, the inverse of `angle_to_vector`.
+    pub fn angle(&self) -> f64 {
+        self.y.atan2(self.x).to_degrees()
+    }
+
+    pub fn lerp(&self, other: Vector, t: f64) -> Vector {
+        *self + (other - *self) * t
+    }
+
+    pub fn distance(&self, other: Vector) -> f64 {
+        (other - *self).length()
+    }
 }
 
 impl Add for Vector {
@@ -75,6 +125,23 @@ impl MulAssign for Vector {
     }
 }
 
+impl Mul<f64> for Vector {
+    type Output = Self;
+
+    fn mul(self, scalar: f64) -> Self {
+        Vector {
+            x: self.x * scalar,
+            y: self.y * scalar,
+        }
+    }
+}
+
+impl MulAssign<f64> for Vector {
+    fn mul_assign(&mut self, scalar: f64) {
+        *self = *self * scalar;
+    }
+}
+
 impl Div for Vector {
     type Output = Self;
 
@@ -92,6 +159,34 @@ impl DivAssign for Vector {
     }
 }
 
+impl Div<f64> for Vector {
+    type Output = Self;
+
+    fn div(self, scalar: f64) -> Self {
+        Vector {
+            x: self.x / scalar,
+            y: self.y / scalar,
+        }
+    }
+}
+
+impl DivAssign<f64> for Vector {
+    fn div_assign(&mut self, scalar: f64) {
+        *self = *self / scalar;
+    }
+}
+
+impl Neg for Vector {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Vector {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
 impl Rem for Vector {
     type Output = Self;
 
@@ -140,11 +235,7 @@ pub fn degree_to_radians(degrees: f64) -> f64 {
 }
 
 pub fn angle_to_vector(mag: f64, theta: f64) -> Vector {
-    let r_rad = degree_to_radians(theta);
-    Vector::new(
-        r_rad.cos() * mag,
-        r_rad.sin() * mag,
-    )
+    Vector::new(mag, 0.0).rotate(theta)
 }
 
 //pub fn point_in_box(point: Vector, bbox: [Vector; 2]) -> bool {
@@ -155,8 +246,23 @@ pub fn angle_to_vector(mag: f64, theta: f64) -> Vector {
 //}
 
 pub fn point_within_radius(point: Vector, source: Vector, radius: f64) -> bool {
-    let delta = ((point.x - source.x).powi(2) + (point.y - source.y).powi(2)).sqrt();
-    delta <= radius
+    point.distance(source) <= radius
+}
+
+/// Shortest offset from `from` to `to` on a playfield that wraps at `bounds`.
+pub fn wrapped_offset(from: Vector, to: Vector, bounds: Vector) -> Vector {
+    let mut d = to - from;
+    if d.x > bounds.x / 2.0 {
+        d.x -= bounds.x;
+    } else if d.x < -bounds.x / 2.0 {
+        d.x += bounds.x;
+    }
+    if d.y > bounds.y / 2.0 {
+        d.y -= bounds.y;
+    } else if d.y < -bounds.y / 2.0 {
+        d.y += bounds.y;
+    }
+    d
 }
 
 pub fn loop_pos(pos: Vector, diameter: f64, bounds: Vector) -> Vector {
@@ -181,12 +287,87 @@ pub fn loop_pos(pos: Vector, diameter: f64, bounds: Vector) -> Vector {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    const EPSILON: f64 = 1e-6;
+
+    fn vector() -> impl Strategy<Value = Vector> {
+        (-1e4..1e4f64, -1e4..1e4f64).prop_map(|(x, y)| Vector::new(x, y))
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= EPSILON * a.abs().max(b.abs()).max(1.0)
+    }
+
+    fn close_vec(a: Vector, b: Vector) -> bool {
+        close(a.x, b.x) && close(a.y, b.y)
+    }
+
+    proptest! {
+        #[test]
+        fn prop_normalize_is_unit(v in vector()) {
+            prop_assume!(v.length() > EPSILON);
+            prop_assert!(close(v.normalize().length(), 1.0));
+            prop_assert!(close(v.normalize().cross(v), 0.0));
+        }
+
+        #[test]
+        fn prop_rotate_keeps_length(v in vector(), deg in -720.0..720.0f64) {
+            prop_assert!(close(v.rotate(deg).length(), v.length()));
+            prop_assert!(close_vec(v.rotate(deg).rotate(-deg), v));
+        }
+
+        #[test]
+        fn prop_angle_round_trips(mag in 1.0..1e4f64, deg in -179.0..179.0f64) {
+            let v = angle_to_vector(mag, deg);
+            prop_assert!(close(v.angle(), deg));
+            prop_assert!(close(v.length(), mag));
+        }
+
+        #[test]
+        fn prop_dot_and_cross(a in vector(), b in vector()) {
+            prop_assert!(close(a.dot(b), b.dot(a)));
+            prop_assert!(close(a.cross(b), -b.cross(a)));
+            prop_assert!(close(a.dot(a), a.length_squared()));
+            // |a|^2 |b|^2 = (a.b)^2 + (a x b)^2
+            let lhs = a.length_squared() * b.length_squared();
+            prop_assert!((lhs - (a.dot(b).powi(2) + a.cross(b).powi(2))).abs() <= 1e-6 * lhs.max(1.0));
+        }
+
+        #[test]
+        fn prop_lerp_ends(a in vector(), b in vector()) {
+            prop_assert!(close_vec(a.lerp(b, 0.0), a));
+            prop_assert!(close_vec(a.lerp(b, 1.0), b));
+            prop_assert!(close(a.lerp(b, 0.5).distance(a), a.distance(b) / 2.0));
+        }
+
+        #[test]
+        fn prop_scalar_ops(v in vector(), k in 0.1..100.0f64) {
+            prop_assert!(close_vec(v * k / k, v));
+            prop_assert!(close_vec(-(-v), v));
+            prop_assert!(close_vec(v + -v, Vector::new_empty()));
+            prop_assert!(close((v * k).length(), v.length() * k));
+        }
+    }
+
+    #[test]
+    fn test_rotate_is_clockwise_on_screen() {
+        // y points down, so +90 turns right into down
+        assert!(close_vec(Vector::new(1.0, 0.0).rotate(90.0), Vector::new(0.0, 1.0)));
+    }
+
+    #[test]
+    fn test_vector_serde() {
+        let v = Vector::new(1.5, -2.0);
+        let s = ron::ser::to_string(&v).unwrap();
+        assert_eq!(ron::de::from_str::<Vector>(&s).unwrap(), v);
+    }
 
     #[test]
     fn test_point_within_radius() {
         let p1 = Vector::new(100.0, 100.0);
         let p2 = Vector::new(90.0, 90.0);
-        assert_eq!(point_within_radius(p1, p2, 15.0), true);
+        assert!(point_within_radius(p1, p2, 15.0));
     }
 }
 
@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+
+use crate::components::roid::{RoidConfig, RoidSize};
+use crate::components::well::{WellKind, WellSpawn};
+use crate::{VIEW_H, VIEW_W};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RoidSpawn {
+    pub size: RoidSize,
+    pub count: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Rule {
+    /// The ship is killed if the wave isn't cleared within this many seconds.
+    TimeLimit(f64),
+    /// Boosters don't fire for the whole wave.
+    NoThrust,
+    /// Multiplies the roids' max spin.
+    RoidSpin(f64),
+    /// Multiplies the roids' speed range.
+    RoidSpeed(f64),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Wave {
+    pub roids: Vec<RoidSpawn>,
+    /// `(min, max)` roid speed, defaults to the range in `RoidConfig`.
+    #[serde(default)]
+    pub speed: Option<(f64, f64)>,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    #[serde(default)]
+    pub wells: Vec<WellSpawn>,
+}
+
+/// How waves past the last defined one are built. Every roid group of
+/// `template` gets `linear * level + quadratic * level^2` extra roids and
+/// speeds grow by `speed_growth` per level past the last defined wave.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Endless {
+    pub template: Wave,
+    pub linear: f64,
+    pub quadratic: f64,
+    pub speed_growth: f64,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Waves {
+    pub waves: Vec<Wave>,
+    pub endless: Endless,
+}
+
+impl Waves {
+    /// Rejects black holes whose horizon reaches half way across the
+    /// playfield, they'd leave roids nowhere to start.
+    pub fn check(&self) -> Result<(), String> {
+        let max_horizon = VIEW_W.min(VIEW_H) / 2.0;
+        let waves = self.waves.iter().enumerate().map(|(i, wave)| (format!("wave {}", i + 1), wave));
+        for (name, wave) in waves.chain(std::iter::once(("the endless template".to_string(), &self.endless.template))) {
+            let too_big = wave.wells.iter().find(|well| well.kind == WellKind::BlackHole && well.horizon >= max_horizon);
+            if let Some(well) = too_big {
+                return Err(format!("{} has a black hole horizon of {}, it has to be under {}", name, well.horizon, max_horizon));
+            }
+        }
+        Ok(())
+    }
+
+    /// Wave for a 1-based `level`.
+    pub fn wave(&self, level: u32) -> Wave {
+        let level = level.max(1);
+        if let Some(wave) = self.waves.get(level as usize - 1) {
+            return wave.clone();
+        }
+        let endless = &self.endless;
+        let past_last = f64::from(level) - self.waves.len() as f64;
+        let extra = (endless.linear * f64::from(level) + endless.quadratic * f64::from(level).powi(2)).round();
+        let speed_scale = 1.0 + endless.speed_growth * past_last;
+        let mut wave = endless.template.clone();
+        for group in &mut wave.roids {
+            group.count = (f64::from(group.count) + extra).max(0.0) as u32;
+        }
+        if speed_scale != 1.0 {
+            wave.rules.push(Rule::RoidSpeed(speed_scale));
+        }
+        wave
+    }
+}
+
+impl Wave {
+    pub fn roid_config(&self, base: &RoidConfig) -> RoidConfig {
+        let mut config = match self.speed {
+            Some((min, max)) => base.with_speed(min, max),
+            None => base.clone(),
+        };
+        for rule in &self.rules {
+            match rule {
+                Rule::RoidSpin(spin) => config = config.scaled(1.0, *spin),
+                Rule::RoidSpeed(speed) => config = config.scaled(*speed, 1.0),
+                _ => {}
+            }
+        }
+        config
+    }
+
+    pub fn time_limit(&self) -> Option<f64> {
+        self.rules.iter().find_map(|rule| match rule {
+            Rule::TimeLimit(secs) => Some(*secs),
+            _ => None,
+        })
+    }
+
+    pub fn allows_thrust(&self) -> bool {
+        !self.rules.contains(&Rule::NoThrust)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::Vector;
+
+    fn default_waves() -> Waves {
+        ron::de::from_str(include_str!("../waves.ron")).unwrap()
+    }
+
+    fn large_roids(wave: &Wave) -> u32 {
+        wave.roids
+            .iter()
+            .filter(|group| group.size == RoidSize::Large)
+            .map(|group| group.count)
+            .sum()
+    }
+
+    #[test]
+    fn test_defined_waves() {
+        let waves = default_waves();
+        assert_eq!(large_roids(&waves.wave(1)), 10);
+        assert_eq!(waves.wave(0), waves.wave(1));
+        assert_eq!(large_roids(&waves.wave(2)), 14);
+    }
+
+    #[test]
+    fn test_endless_waves() {
+        let waves = default_waves();
+        let last = waves.waves.len() as u32;
+        for level in last + 1..last + 10 {
+            assert_eq!(large_roids(&waves.wave(level)), 10 + level * level);
+        }
+    }
+
+    #[test]
+    fn test_wells_are_placed_per_wave() {
+        let waves = default_waves();
+        assert!(waves.wave(1).wells.is_empty());
+        assert!(waves.waves.iter().any(|wave| !wave.wells.is_empty()));
+    }
+
+    #[test]
+    fn test_huge_horizons_are_rejected() {
+        let mut waves = default_waves();
+        assert_eq!(waves.check(), Ok(()));
+        let well = WellSpawn { kind: WellKind::BlackHole, pos: Vector::new_empty(), strength: 1.0, horizon: 400.0 };
+        waves.waves[1].wells.push(well);
+        assert_eq!(waves.check(), Err("wave 2 has a black hole horizon of 400, it has to be under 384".to_string()));
+    }
+
+    #[test]
+    fn test_endless_speed_growth() {
+        let mut waves = default_waves();
+        waves.endless.speed_growth = 0.5;
+        let next = waves.waves.len() as u32 + 2;
+        assert!(waves.wave(next).rules.contains(&Rule::RoidSpeed(2.0)));
+    }
+}
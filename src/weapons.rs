@@ -0,0 +1,214 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::components::laser::FireMode;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum WeaponKind {
+    /// One shot straight ahead.
+    Single,
+    /// `count` shots fanned out evenly over `angle` degrees.
+    Spread { count: u32, angle: f64 },
+    /// One shot, knocked up to `jitter` degrees off course.
+    Rapid { jitter: f64 },
+    /// One shot that carries on through everything it hits.
+    Beam,
+    /// A missile that locks on to the nearest roid within `range` px and
+    /// `cone` degrees of its heading, turns towards it at up to `turn_rate`
+    /// degrees per second for `fuel` seconds and blows up everything within
+    /// `blast_radius` px of where it hits.
+    Missile { turn_rate: f64, cone: f64, range: f64, fuel: f64, blast_radius: f64 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Weapon {
+    pub name: String,
+    pub kind: WeaponKind,
+    /// Seconds between shots.
+    pub cooldown: f64,
+    pub speed: f64,
+    pub lifetime: f64,
+    /// Part of the sprite sheet the projectile is drawn with, and its scale.
+    pub sprite: [f64; 4],
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    #[serde(default = "default_damage")]
+    pub damage: u32,
+    /// Mass handed to the roid a projectile hits.
+    #[serde(default)]
+    pub mass: f64,
+    /// Shots per wave, unlimited when left out.
+    #[serde(default)]
+    pub ammo: Option<u32>,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+fn default_damage() -> u32 {
+    1
+}
+
+impl Weapon {
+    /// Rotations to fire projectiles at for a ship facing `rot`.
+    pub fn directions<R: Rng + ?Sized>(&self, rot: f64, rng: &mut R) -> Vec<f64> {
+        match self.kind {
+            WeaponKind::Spread { count, angle } if count > 1 => {
+                let step = angle / f64::from(count - 1);
+                (0..count).map(|i| rot - angle / 2.0 + step * f64::from(i)).collect()
+            }
+            WeaponKind::Rapid { jitter } if jitter > 0.0 => vec![rot + rng.gen_range(-jitter, jitter)],
+            _ => vec![rot],
+        }
+    }
+
+    pub fn pierces(&self) -> bool {
+        self.kind == WeaponKind::Beam
+    }
+}
+
+/// The weapons a ship carries, which one is selected, how long until it
+/// can fire again and the ammo left for each.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Arsenal {
+    weapons: Vec<Weapon>,
+    ammo: Vec<Option<u32>>,
+    current: usize,
+    cooldown: f64,
+    trigger_held: bool,
+}
+
+impl Arsenal {
+    /// `weapons` must not be empty.
+    pub fn new(weapons: Vec<Weapon>) -> Self {
+        assert!(!weapons.is_empty(), "an arsenal needs at least one weapon");
+        Self {
+            ammo: weapons.iter().map(|weapon| weapon.ammo).collect(),
+            weapons,
+            current: 0,
+            cooldown: 0.0,
+            trigger_held: false,
+        }
+    }
+
+    pub fn current(&self) -> &Weapon {
+        &self.weapons[self.current]
+    }
+
+    /// Ammo left for the selected weapon, `None` if it is unlimited.
+    pub fn ammo(&self) -> Option<u32> {
+        self.ammo[self.current]
+    }
+
+    /// Refills every weapon's ammo.
+    pub fn restock(&mut self) {
+        for (ammo, weapon) in self.ammo.iter_mut().zip(&self.weapons) {
+            *ammo = weapon.ammo;
+        }
+    }
+
+    /// Selects the next weapon, wrapping around to the first.
+    pub fn cycle(&mut self) {
+        self.current = (self.current + 1) % self.weapons.len();
+    }
+
+    pub fn cool_down(&mut self, dt: f64) {
+        self.cooldown = (self.cooldown - dt).max(0.0);
+    }
+
+    /// Whether the trigger being `held` this tick asks for a shot. Semi-auto
+    /// only asks on the tick the trigger is pulled.
+    pub fn trigger(&mut self, held: bool, mode: FireMode) -> bool {
+        let pulled = held && !self.trigger_held;
+        self.trigger_held = held;
+        match mode {
+            FireMode::FullAuto => held,
+            FireMode::SemiAuto => pulled,
+        }
+    }
+
+    /// The selected weapon if it is ready and has ammo, starting its
+    /// cooldown and using up a shot.
+    pub fn fire(&mut self) -> Option<&Weapon> {
+        if self.cooldown > 0.0 || self.ammo() == Some(0) {
+            return None;
+        }
+        if let Some(ammo) = &mut self.ammo[self.current] {
+            *ammo -= 1;
+        }
+        self.cooldown = self.current().cooldown;
+        Some(self.current())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weapon(name: &str, kind: WeaponKind) -> Weapon {
+        Weapon {
+            name: name.to_string(),
+            kind,
+            cooldown: 0.1,
+            speed: 10.0,
+            lifetime: 1.0,
+            sprite: [0.0, 0.0, 4.0, 4.0],
+            scale: 1.0,
+            damage: 1,
+            mass: 0.0,
+            ammo: None,
+        }
+    }
+
+    #[test]
+    fn test_spread_fans_out_evenly() {
+        let mut rng = rand_pcg::Pcg64Mcg::new(1);
+        let spread = weapon("spread", WeaponKind::Spread { count: 5, angle: 40.0 });
+        assert_eq!(spread.directions(90.0, &mut rng), vec![70.0, 80.0, 90.0, 100.0, 110.0]);
+        let jittery = weapon("rapid", WeaponKind::Rapid { jitter: 5.0 });
+        for _ in 0..20 {
+            let dir = jittery.directions(0.0, &mut rng)[0];
+            assert!(dir.abs() <= 5.0);
+        }
+    }
+
+    #[test]
+    fn test_arsenal_cools_down_and_cycles() {
+        let mut arsenal = Arsenal::new(vec![weapon("a", WeaponKind::Single), weapon("b", WeaponKind::Beam)]);
+        assert!(arsenal.fire().is_some());
+        assert!(arsenal.fire().is_none());
+        arsenal.cool_down(0.1);
+        assert!(arsenal.fire().is_some());
+        arsenal.cycle();
+        assert_eq!(arsenal.current().name, "b");
+        assert!(arsenal.current().pierces());
+        arsenal.cycle();
+        assert_eq!(arsenal.current().name, "a");
+    }
+
+    #[test]
+    fn test_semi_auto_fires_once_per_pull() {
+        let mut arsenal = Arsenal::new(vec![weapon("a", WeaponKind::Single)]);
+        let held = [true, true, false, true];
+        let semi: Vec<bool> = held.iter().map(|&h| arsenal.trigger(h, FireMode::SemiAuto)).collect();
+        assert_eq!(semi, vec![true, false, false, true]);
+        let full: Vec<bool> = held.iter().map(|&h| arsenal.trigger(h, FireMode::FullAuto)).collect();
+        assert_eq!(full, held.to_vec());
+    }
+
+    #[test]
+    fn test_ammo_runs_out_until_restocked() {
+        let mut missile = weapon("missile", WeaponKind::Single);
+        missile.ammo = Some(2);
+        let mut arsenal = Arsenal::new(vec![missile]);
+        for _ in 0..2 {
+            assert!(arsenal.fire().is_some());
+            arsenal.cool_down(1.0);
+        }
+        assert_eq!(arsenal.ammo(), Some(0));
+        assert!(arsenal.fire().is_none());
+        arsenal.restock();
+        assert!(arsenal.fire().is_some());
+    }
+}
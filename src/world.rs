@@ -0,0 +1,411 @@
+use piston_window::UpdateArgs;
+use rand::SeedableRng;
+use rand_pcg::Pcg64Mcg;
+
+use crate::ai::Perception;
+use crate::components::{Body, Transform};
+use crate::components::ship::Ship;
+use crate::components::projectile::{Bounded, Projectile};
+use crate::components::roid::{Roid, RoidConfig};
+use crate::components::well::Well;
+use crate::ecs::{Ecs, Entity};
+use crate::events::{Death, GameEvent, Subscriber};
+use crate::scoring::Scoring;
+use crate::snapshot::Snapshot;
+use crate::stats::SessionStats;
+use crate::systems;
+use crate::spatial::SpatialGrid;
+use crate::utils::{Vector, point_within_radius};
+use crate::waves::{Wave, Waves};
+use crate::weapons::Arsenal;
+use crate::GameConfig;
+
+/// Side of a spatial grid cell, about the size of a large roid.
+const GRID_CELL: f64 = 64.0;
+
+/// What happened during one `World::update`.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Tick {
+    pub points: u32,
+    pub roids_destroyed: u32,
+    pub collision_checks: u32,
+    pub ship_died: bool,
+    pub wave_cleared: bool,
+}
+
+/// The simulation, with no window or textures attached, so it can be stepped
+/// headless and drawn by whoever owns it.
+pub struct World {
+    pub ecs: Ecs,
+    /// The player's ship, which lives for as long as the world does.
+    pub ship: Entity,
+    pub scoring: Scoring,
+    /// Stats for the game in progress.
+    pub stats: SessionStats,
+    pub level: u32,
+    pub wave: Wave,
+    pub wave_time: f64,
+    grid: SpatialGrid,
+    rng: Pcg64Mcg,
+    waves: Waves,
+    config: GameConfig,
+    /// Published since the start of the tick.
+    events: Vec<GameEvent>,
+}
+
+impl World {
+    pub fn new(config: GameConfig, waves: Waves, seed: u64) -> Self {
+        let mut ecs = Ecs::new();
+        let ship = Ship::spawn(&mut ecs, config.ship_config.clone());
+        let mut weapons = vec![config.laser_config.weapon(config.ship_config.laser_timer())];
+        weapons.extend(config.weapons.iter().cloned());
+        ecs.arsenals.insert(ship, Arsenal::new(weapons));
+        let mut world = Self {
+            ecs,
+            ship,
+            scoring: Scoring::new(config.scoring.clone()),
+            stats: SessionStats::default(),
+            level: 1,
+            wave: waves.wave(1),
+            wave_time: 0.0,
+            grid: SpatialGrid::new(GRID_CELL),
+            rng: Pcg64Mcg::seed_from_u64(seed),
+            waves,
+            config,
+            events: Vec::new(),
+        };
+        world.start_wave(1);
+        world
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn ship(&self) -> &Ship {
+        self.ecs.ships.get(self.ship).unwrap()
+    }
+
+    pub fn ship_mut(&mut self) -> &mut Ship {
+        self.ecs.ships.get_mut(self.ship).unwrap()
+    }
+
+    pub fn ship_transform(&self) -> &Transform {
+        self.ecs.transforms.get(self.ship).unwrap()
+    }
+
+    pub fn ship_body(&self) -> &Body {
+        self.ecs.bodies.get(self.ship).unwrap()
+    }
+
+    pub fn arsenal(&self) -> &Arsenal {
+        self.ecs.arsenals.get(self.ship).unwrap()
+    }
+
+    /// Selects the ship's next weapon.
+    pub fn cycle_weapon(&mut self) {
+        self.ecs.arsenals.get_mut(self.ship).unwrap().cycle();
+    }
+
+    pub fn perception(&self) -> Perception {
+        let rules = &self.config.laser_config;
+        Perception::new(&self.ecs, self.ship, self.arsenal().current().speed, rules.inherit_velocity)
+    }
+
+    /// A copy of the run's state, see `restore`.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            ecs: self.ecs.clone(),
+            ship: self.ship,
+            scoring: self.scoring.clone(),
+            stats: self.stats,
+            level: self.level,
+            wave: self.wave.clone(),
+            wave_time: self.wave_time,
+            rng: self.rng.clone(),
+        }
+    }
+
+    /// Puts the run back the way it was when `snapshot` was taken. The world
+    /// has to have been made with the same config and waves.
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        self.ecs = snapshot.ecs;
+        self.ship = snapshot.ship;
+        self.scoring = snapshot.scoring;
+        self.stats = snapshot.stats;
+        self.level = snapshot.level;
+        self.wave = snapshot.wave;
+        self.wave_time = snapshot.wave_time;
+        self.rng = snapshot.rng;
+        self.events.clear();
+    }
+
+    /// Events published since the start of the tick.
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    /// Takes the events published since the start of the tick, for the
+    /// subscribers outside the world.
+    pub fn drain_events(&mut self) -> std::vec::Drain<'_, GameEvent> {
+        self.events.drain(..)
+    }
+
+    /// Keeps the scoring and stats up to date with `event` and queues it,
+    /// followed by an `ExtraLife` for every life the scoring handed out.
+    fn publish(&mut self, event: GameEvent) {
+        let lives = self.scoring.lives;
+        self.scoring.notify(&event);
+        self.stats.notify(&event);
+        self.events.push(event);
+        for lives in lives + 1..=self.scoring.lives {
+            self.events.push(GameEvent::ExtraLife { lives });
+        }
+    }
+
+    pub fn update(&mut self, args: UpdateArgs) -> Tick {
+        let mut tick = Tick::default();
+        self.move_entities(args);
+        self.collide(&mut tick);
+        self.spawn(&mut tick);
+        tick
+    }
+
+    /// Applies the wave rules and runs the ship controls, firing the selected
+    /// weapon if the ship wants to, then lets the systems steer, pull, move
+    /// and age everything and black holes swallow whatever crossed their horizon.
+    /// Shots that go away here without having hit anything count as misses.
+    pub fn move_entities(&mut self, args: UpdateArgs) {
+        self.scoring.begin_tick();
+        self.events.clear();
+        let mut events = Vec::new();
+        self.grid.rebuild(&self.ecs);
+        let ecs = &mut self.ecs;
+        let rules = &self.config.laser_config;
+        let free_shots = rules.max_shots.map_or(usize::MAX, |max| max.saturating_sub(ecs.projectiles.len()));
+        let ship = ecs.ships.get_mut(self.ship).unwrap();
+        let transform = ecs.transforms.get(self.ship).unwrap();
+        let body = ecs.bodies.get_mut(self.ship).unwrap();
+        self.wave_time += args.dt;
+        if let Some(limit) = self.wave.time_limit() {
+            if self.wave_time > limit && !ship.dead {
+                ship.kill();
+                events.push(GameEvent::ShipKilled { pos: transform.pos, death: Death::OutOfTime });
+            }
+        }
+        ship.boosters_enabled = self.wave.allows_thrust();
+        ship.update(transform, body, args);
+        if !ship.dead {
+            self.stats.time_alive += args.dt;
+            self.stats.distance += body.vel.length() * args.dt * 60.0;
+        }
+        if let Some(renderable) = ecs.renderables.get_mut(self.ship) {
+            renderable.tint = ship.tint();
+        }
+        let arsenal = ecs.arsenals.get_mut(self.ship).unwrap();
+        arsenal.cool_down(args.dt);
+        if arsenal.trigger(ship.actions.is_shooting, rules.fire_mode) && free_shots > 0 {
+            if let Some(weapon) = arsenal.fire() {
+                let weapon = weapon.clone();
+                let vel = if rules.inherit_velocity { body.vel } else { Vector::new_empty() };
+                let pos = ship.get_laser_pos(transform);
+                for rot in weapon.directions(transform.rot, &mut self.rng).into_iter().take(free_shots) {
+                    let shot = Projectile::spawn(ecs, &weapon, vel, pos, rot);
+                    if !rules.wrap_shots {
+                        ecs.bounded.insert(shot, Bounded);
+                    }
+                    events.push(GameEvent::LaserFired { pos, rot });
+                }
+            }
+        }
+
+        let unspent: Vec<Entity> = ecs.projectiles.iter()
+            .filter(|(_, projectile)| projectile.hits.is_empty())
+            .map(|(entity, _)| entity)
+            .collect();
+
+        systems::steer_missiles(ecs, &self.grid, args.dt);
+        systems::gravity(ecs, args.dt);
+        systems::integrate(ecs, args.dt);
+        systems::age(ecs, args.dt);
+        for entity in systems::swallow(ecs) {
+            let ship = ecs.ships.get_mut(entity).unwrap();
+            if !ship.dead {
+                ship.kill();
+                let pos = ecs.transforms.get(entity).unwrap().pos;
+                events.push(GameEvent::ShipKilled { pos, death: Death::BlackHole });
+            }
+        }
+        for shot in unspent {
+            if !ecs.entities.is_alive(shot) {
+                events.push(GameEvent::ShotMissed);
+            }
+        }
+        for event in events {
+            self.publish(event);
+        }
+    }
+
+    /// Projectiles push and damage the roids they hit, missiles blow up
+    /// everything around them, roids break up once they run out of health
+    /// and kill the ship. Roids bounce off each other first when the config
+    /// asks for it. Only pairs the grid puts near each other are checked.
+    pub fn collide(&mut self, tick: &mut Tick) {
+        if let Some(restitution) = self.config.roid_config.bounce() {
+            tick.collision_checks += systems::bounce_roids(&mut self.ecs, restitution);
+        }
+        self.grid.rebuild(&self.ecs);
+        let mut nearby = Vec::new();
+        let shots: Vec<Entity> = self.ecs.projectiles.entities().collect();
+        for shot in shots {
+            let pos = self.ecs.transforms.get(shot).unwrap().pos;
+            self.grid.query(pos, self.grid.max_radius(), &mut nearby);
+            for &roid in &nearby {
+                let (roid_pos, radius) = match (self.ecs.transforms.get(roid), self.ecs.colliders.get(roid)) {
+                    (Some(transform), Some(collider)) if self.ecs.roids.contains(roid) => (transform.pos, collider.radius),
+                    _ => continue,
+                };
+                let projectile = match self.ecs.projectiles.get_mut(shot) {
+                    Some(projectile) => projectile,
+                    None => break,
+                };
+                if projectile.hits.contains(&roid) {
+                    continue;
+                }
+                tick.collision_checks += 1;
+                if !point_within_radius(pos, roid_pos, radius) {
+                    continue;
+                }
+                let damage = projectile.damage;
+                let first_hit = projectile.hits.is_empty();
+                let shot_body = *self.ecs.bodies.get(shot).unwrap();
+                let blast_radius = self.ecs.missiles.get(shot).map(|missile| missile.blast_radius);
+                if projectile.pierce {
+                    projectile.hits.push(roid);
+                } else {
+                    self.ecs.despawn(shot);
+                }
+                self.publish(GameEvent::RoidHit { pos, first_hit });
+                self.ecs.bodies.get_mut(roid).unwrap().absorb(shot_body.vel, shot_body.mass);
+                match blast_radius {
+                    Some(blast_radius) => self.detonate(pos, blast_radius, damage, tick),
+                    None => self.damage_roid(roid, damage, tick),
+                }
+            }
+        }
+
+        let ship_pos = self.ecs.transforms.get(self.ship).unwrap().pos;
+        let ship_radius = self.ecs.colliders.get(self.ship).unwrap().radius;
+        self.grid.query(ship_pos, ship_radius + self.grid.max_radius(), &mut nearby);
+        for &roid in &nearby {
+            let (roid_pos, radius) = match (self.ecs.transforms.get(roid), self.ecs.colliders.get(roid)) {
+                (Some(transform), Some(collider)) if self.ecs.roids.contains(roid) => (transform.pos, collider.radius),
+                _ => continue,
+            };
+            tick.collision_checks += 1;
+            if point_within_radius(ship_pos, roid_pos, radius + ship_radius) {
+                if !self.ship().dead {
+                    self.ship_mut().kill();
+                    self.publish(GameEvent::ShipKilled { pos: ship_pos, death: Death::Roid });
+                }
+                break;
+            }
+        }
+    }
+
+    /// Deals `damage` to every roid within `blast_radius` of `pos`.
+    fn detonate(&mut self, pos: Vector, blast_radius: f64, damage: u32, tick: &mut Tick) {
+        let mut caught = Vec::new();
+        self.grid.query(pos, blast_radius + self.grid.max_radius(), &mut caught);
+        for roid in caught {
+            let (roid_pos, radius) = match (self.ecs.transforms.get(roid), self.ecs.colliders.get(roid)) {
+                (Some(transform), Some(collider)) if self.ecs.roids.contains(roid) => (transform.pos, collider.radius),
+                _ => continue,
+            };
+            tick.collision_checks += 1;
+            if point_within_radius(roid_pos, pos, blast_radius + radius) {
+                self.damage_roid(roid, damage, tick);
+            }
+        }
+    }
+
+    /// Deals `damage` to `roid`, scoring it and breaking it up if that destroys it.
+    fn damage_roid(&mut self, roid: Entity, damage: u32, tick: &mut Tick) {
+        let (destroyed, size) = match self.ecs.roids.get_mut(roid) {
+            Some(health) => (health.hit(damage), health.size),
+            None => return,
+        };
+        if !destroyed {
+            return;
+        }
+        let diameter = self.ecs.colliders.get(roid).unwrap().radius * 2.0;
+        let pos = self.ecs.transforms.get(roid).unwrap().pos;
+        self.publish(GameEvent::RoidDestroyed { size, pos, diameter });
+        tick.roids_destroyed += 1;
+        let roid_config = self.wave.roid_config(&self.config.roid_config);
+        Roid::split(&mut self.ecs, roid, &roid_config, &mut self.rng);
+        self.ecs.despawn(roid);
+    }
+
+    /// Starts the next wave once this one is cleared, with the wave bonuses.
+    /// A dead ship replays the wave while it has lives left, otherwise the
+    /// game starts over. Adds up the points scored this tick.
+    pub fn spawn(&mut self, tick: &mut Tick) {
+        let dead = self.ship().dead;
+        if self.ecs.roids.is_empty() && !dead {
+            tick.wave_cleared = true;
+            self.publish(GameEvent::WaveCleared { level: self.level, wave_time: self.wave_time });
+            self.reset_ship();
+            self.start_wave(self.level + 1);
+        } else if dead {
+            tick.ship_died = true;
+            self.stats.score = self.scoring.score;
+            let level = if self.scoring.died() {
+                let stats = std::mem::take(&mut self.stats);
+                self.publish(GameEvent::GameOver { stats });
+                1
+            } else {
+                self.level
+            };
+            self.reset_ship();
+            self.start_wave(level);
+            self.ship_mut().unkill();
+        }
+        tick.points += self.scoring.points();
+    }
+
+    fn reset_ship(&mut self) {
+        let transform = self.ecs.transforms.get_mut(self.ship).unwrap();
+        let body = self.ecs.bodies.get_mut(self.ship).unwrap();
+        Ship::reset(transform, body);
+    }
+
+    /// Replaces the roids and wells with the ones for `level`.
+    fn start_wave(&mut self, level: u32) {
+        self.level = level;
+        self.wave = self.waves.wave(level);
+        self.wave_time = 0.0;
+        let old: Vec<Entity> = self.ecs.roids.entities().chain(self.ecs.wells.entities()).collect();
+        for entity in old {
+            self.ecs.despawn(entity);
+        }
+        // wells first, so roids can stay out of the black holes
+        for well in &self.wave.wells {
+            Well::spawn(&mut self.ecs, well);
+        }
+        spawn_roids(&mut self.ecs, &mut self.rng, &self.wave, &self.config.roid_config);
+        if let Some(arsenal) = self.ecs.arsenals.get_mut(self.ship) {
+            arsenal.restock();
+        }
+        self.scoring.start_wave();
+    }
+}
+
+fn spawn_roids(ecs: &mut Ecs, rng: &mut Pcg64Mcg, wave: &Wave, config: &RoidConfig) {
+    let roid_config = wave.roid_config(config);
+    for group in &wave.roids {
+        for _ in 0..group.count {
+            Roid::spawn(ecs, &roid_config, group.size, rng);
+        }
+    }
+}
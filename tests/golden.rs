@@ -0,0 +1,148 @@
+//! Renders seeded scenes with the CPU renderer and compares them against the
+//! reference PNGs in tests/golden. Set STROIDS_BLESS=1 to write new references
+//! after a change that is meant to move pixels.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use image::{Rgba, RgbaImage};
+use piston_window::UpdateArgs;
+
+use stroids_clone::assets::Assets;
+use stroids_clone::debug::DebugOverlay;
+use stroids_clone::render::draw_frame;
+use stroids_clone::render::soft::SoftRenderer;
+use stroids_clone::utils::Vector;
+use stroids_clone::world::World;
+use stroids_clone::{load_config, load_waves, VIEW_H, VIEW_W};
+
+/// How far apart a channel can be before the pixel counts as different.
+const TOLERANCE: u8 = 16;
+/// Different pixels allowed before the frame fails, enough for rounding
+/// along edges but not for a moved or missing sprite.
+const MAX_DIFFERENT: usize = 64;
+
+fn world(seed: u64) -> World {
+    let assets = Assets::new(None);
+    World::new(load_config(None, &assets).unwrap(), load_waves(None, &assets).unwrap(), seed)
+}
+
+/// Stands in for the sprite sheet so the references don't change when the
+/// art does. Every texel has its own colour, so a wrong rect or a wrong
+/// turn still shows up as the wrong colours.
+fn atlas() -> RgbaImage {
+    RgbaImage::from_fn(400, 300, |x, y| {
+        let checker = if (x / 8 + y / 8) % 2 == 0 { 255 } else { 96 };
+        Rgba([(x * 255 / 400) as u8, (y * 255 / 300) as u8, checker, 255])
+    })
+}
+
+fn render(world: &World, debug: &DebugOverlay, stats: &[String]) -> RgbaImage {
+    let mut renderer = SoftRenderer::new(VIEW_W as u32, VIEW_H as u32, atlas());
+    draw_frame(&mut renderer, world, debug, stats);
+    renderer.frame().clone()
+}
+
+fn clear_roids(world: &mut World) {
+    let roids: Vec<_> = world.ecs.roids.entities().collect();
+    for roid in roids {
+        world.ecs.despawn(roid);
+    }
+}
+
+/// Compares `actual` against tests/golden/`name`.png, leaving the frame and
+/// a diff next to the test binaries when they don't match.
+fn check(name: &str, actual: &RgbaImage) {
+    let reference = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(format!("{}.png", name));
+    if env::var_os("STROIDS_BLESS").is_some() {
+        fs::create_dir_all(reference.parent().unwrap()).unwrap();
+        actual.save(&reference).unwrap();
+        return;
+    }
+    let out = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    let actual_path = out.join(format!("{}.actual.png", name));
+    let save_actual = || {
+        fs::create_dir_all(&out).unwrap();
+        actual.save(&actual_path).unwrap();
+    };
+    let expected = match image::open(&reference) {
+        Ok(expected) => expected.to_rgba(),
+        Err(e) => {
+            save_actual();
+            panic!("{}: {}, run with STROIDS_BLESS=1 to create it", reference.display(), e);
+        }
+    };
+    assert_eq!(expected.dimensions(), actual.dimensions(), "{} changed size", name);
+    let (diff, different) = diff(&expected, actual);
+    if different > MAX_DIFFERENT {
+        save_actual();
+        let diff_path = out.join(format!("{}.diff.png", name));
+        diff.save(&diff_path).unwrap();
+        panic!(
+            "{} has {} pixels different from {}, see {} and {}",
+            name, different, reference.display(), actual_path.display(), diff_path.display(),
+        );
+    }
+}
+
+/// The expected frame dimmed with the pixels that differ in red, and how many there are.
+fn diff(expected: &RgbaImage, actual: &RgbaImage) -> (RgbaImage, usize) {
+    let mut different = 0;
+    let diff = RgbaImage::from_fn(expected.width(), expected.height(), |x, y| {
+        let (e, a) = (expected.get_pixel(x, y), actual.get_pixel(x, y));
+        if (0..4).any(|i| (i16::from(e[i]) - i16::from(a[i])).abs() > i16::from(TOLERANCE)) {
+            different += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            Rgba([e[0] / 4, e[1] / 4, e[2] / 4, 255])
+        }
+    });
+    (diff, different)
+}
+
+#[test]
+fn test_ship_rotated() {
+    let mut world = world(1);
+    clear_roids(&mut world);
+    world.ecs.transforms.get_mut(world.ship).unwrap().rot = 35.0;
+    check("ship_rotated", &render(&world, &DebugOverlay::new(), &[]));
+}
+
+#[test]
+fn test_roids_at_edges() {
+    let mut world = world(2);
+    let spots = [
+        (0.0, 300.0), (VIEW_W, 500.0), (400.0, 0.0), (700.0, VIEW_H),
+        (0.0, 0.0), (VIEW_W - 10.0, VIEW_H - 10.0), (200.0, 200.0),
+    ];
+    let roids: Vec<_> = world.ecs.roids.entities().collect();
+    for (i, roid) in roids.into_iter().enumerate() {
+        match spots.get(i) {
+            Some(&(x, y)) => {
+                let transform = world.ecs.transforms.get_mut(roid).unwrap();
+                transform.pos = Vector::new(x, y);
+                transform.rot = 25.0 * i as f64;
+            }
+            None => world.ecs.despawn(roid),
+        }
+    }
+    check("roids_at_edges", &render(&world, &DebugOverlay::new(), &[]));
+}
+
+#[test]
+fn test_debug_overlay_and_hud() {
+    let mut world = world(3);
+    for i in 0..90 {
+        let actions = &mut world.ship_mut().actions;
+        actions.rotate_cw = i < 20;
+        actions.fire_boosters = (30..50).contains(&i);
+        actions.is_shooting = i % 3 == 0;
+        world.update(UpdateArgs { dt: 1.0 / 60.0 });
+    }
+    world.scoring.score = 1234;
+    let mut debug = DebugOverlay::new();
+    debug.toggle();
+    let stats = vec!["Frame: 16.7ms".to_string(), "Entities: 42".to_string()];
+    check("debug_overlay_and_hud", &render(&world, &debug, &stats));
+}